@@ -0,0 +1,117 @@
+//! Benchmarks for the hot paths of serving an embed.
+//!
+//! Record a baseline before making changes with
+//! `cargo bench --bench work_metadata -- --save-baseline main` and compare
+//! against it afterwards with `-- --baseline main`; criterion reports any
+//! regression outside its noise threshold.
+//!
+//! Absolute timings depend too much on the machine to be worth writing down
+//! here. Instead the bench workflow runs these against the base branch and
+//! then the pull request on the same runner, reporting any mean more than
+//! 10% slower.
+//!
+//! Selectors compile once per process, so parsing with cold selectors can't
+//! be repeated under criterion. It is timed once before the parsing
+//! benchmarks and printed on its own.
+
+use std::time::Instant;
+
+use ao3_embed::ao3::{meta::{WorkMetadata, WorkTemplate, validate_selectors}, platform::Platform};
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use scraper::{Html, Selector};
+
+const BLURB: &str = r#"
+<ol class="work index group">
+<li class="work blurb group" id="work_123456" role="article">
+    <div class="header module">
+        <h4 class="heading">
+            <a href="/works/123456">The Benchmark Fic</a>
+            by
+            <a rel="author" href="/users/example/pseuds/example">example</a>
+        </h4>
+        <h5 class="fandoms heading">
+            <a class="tag" href="/tags/Original%20Work/works">Original Work</a>
+        </h5>
+        <ul class="required-tags">
+            <li><a class="help symbol question modal"><span class="rating-teen rating" title="Teen And Up Audiences"><span class="text">Teen And Up Audiences</span></span></a></li>
+        </ul>
+        <p class="datetime">2023-03-07</p>
+    </div>
+    <ul class="tags commas">
+        <li class="warnings"><strong><a class="tag" href="/tags/No%20Archive%20Warnings%20Apply/works">No Archive Warnings Apply</a></strong></li>
+        <li class="relationships"><a class="tag" href="/tags/A*s*B/works">Character A/Character B</a></li>
+        <li class="relationships"><a class="tag" href="/tags/C%20*a*%20D/works">Character C &amp; Character D</a></li>
+        <li class="characters"><a class="tag" href="/tags/Character%20A/works">Character A</a></li>
+        <li class="characters"><a class="tag" href="/tags/Character%20B/works">Character B</a></li>
+        <li class="freeforms"><a class="tag" href="/tags/Fluff/works">Fluff</a></li>
+        <li class="freeforms"><a class="tag" href="/tags/Angst/works">Angst</a></li>
+        <li class="freeforms"><a class="tag" href="/tags/Slow%20Burn/works">Slow Burn</a></li>
+    </ul>
+    <blockquote class="userstuff summary"><p>A work that exists to be parsed over and over again.</p></blockquote>
+    <dl class="stats">
+        <dt class="language">Language:</dt><dd class="language">English</dd>
+        <dt class="words">Words:</dt><dd class="words">123,456</dd>
+        <dt class="chapters">Chapters:</dt><dd class="chapters"><a href="/works/123456/chapters/1">12</a>/20</dd>
+        <dt class="comments">Comments:</dt><dd class="comments"><a href="/works/123456?show_comments=true">345</a></dd>
+        <dt class="kudos">Kudos:</dt><dd class="kudos"><a href="/works/123456/kudos">6,789</a></dd>
+        <dt class="bookmarks">Bookmarks:</dt><dd class="bookmarks"><a href="/works/123456/bookmarks">210</a></dd>
+        <dt class="hits">Hits:</dt><dd class="hits">98,765</dd>
+    </dl>
+</li>
+</ol>
+"#;
+
+fn fixture() -> WorkMetadata {
+    let html = Html::parse_fragment(BLURB);
+    let blurb = Selector::parse("li.work.blurb").unwrap();
+
+    WorkMetadata::from_bookmark_blurb(html.select(&blurb).next().unwrap()).unwrap()
+}
+
+fn parsing(c: &mut Criterion) {
+    let blurb = Selector::parse("li.work.blurb").unwrap();
+
+    let cold = Instant::now();
+    let html = Html::parse_fragment(BLURB);
+    black_box(WorkMetadata::from_bookmark_blurb(html.select(&blurb).next().unwrap()).unwrap());
+    println!("parse blurb with cold selectors: {:?} (single run)", cold.elapsed());
+
+    // Selectors are compiled lazily on first use, compile them up front so
+    // that cost doesn't land in whichever benchmark happens to run first.
+    validate_selectors();
+
+    c.bench_function("parse blurb including html", |b| b.iter(|| {
+        let html = Html::parse_fragment(black_box(BLURB));
+        WorkMetadata::from_bookmark_blurb(html.select(&blurb).next().unwrap()).unwrap()
+    }));
+
+    let html = Html::parse_fragment(BLURB);
+
+    c.bench_function("parse blurb from parsed html", |b| b.iter(|| {
+        WorkMetadata::from_bookmark_blurb(black_box(html.select(&blurb).next().unwrap())).unwrap()
+    }));
+}
+
+fn rendering(c: &mut Criterion) {
+    let work = fixture();
+
+    c.bench_function("render and minify work.html", |b| b.iter(|| {
+        let template: WorkTemplate = black_box(&work).format_for_platform(Platform::Discord);
+        template.render_html().unwrap()
+    }));
+
+    c.bench_function("as_json_ld", |b| b.iter(|| black_box(&work).as_json_ld()));
+}
+
+fn serialization(c: &mut Criterion) {
+    let work = fixture();
+
+    c.bench_function("to_bytes", |b| b.iter(|| black_box(&work).to_bytes().unwrap()));
+
+    let bytes = work.to_bytes().unwrap();
+
+    c.bench_function("from_bytes", |b| b.iter(|| WorkMetadata::from_bytes(black_box(&bytes)).unwrap()));
+}
+
+criterion_group!(benches, parsing, rendering, serialization);
+criterion_main!(benches);
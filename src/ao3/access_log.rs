@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+/// A single served request to a work page.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessRecord {
+    /// Unix timestamp in seconds.
+    pub timestamp: i64,
+    pub work_id: u64,
+    pub cache_hit: bool,
+    pub response_time_ms: u64,
+    pub user_agent_hash: String,
+    /// The hex [`PrivacyHash`](super::privacy::PrivacyHash) author hash, only
+    /// recorded with `ANALYTICS_PRIVACY=true`.
+    pub author_hash: Option<String>,
+}
+
+/// An SQLite backed log of served work pages.
+pub struct AccessLog {
+    connection: Mutex<Connection>,
+}
+
+impl AccessLog {
+    /// Opens the database at `path`, creating the schema if needed.
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS access_log (
+                timestamp INTEGER NOT NULL,
+                work_id INTEGER NOT NULL,
+                cache_hit INTEGER NOT NULL,
+                response_time_ms INTEGER NOT NULL,
+                user_agent_hash TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS access_log_work_id ON access_log (work_id, timestamp);",
+        )?;
+
+        // Logs created before author hashes were recorded.
+        if connection.prepare("SELECT author_hash FROM access_log LIMIT 0").is_err() {
+            connection.execute_batch("ALTER TABLE access_log ADD COLUMN author_hash TEXT;")?;
+        }
+
+        Ok(AccessLog { connection: Mutex::new(connection) })
+    }
+
+    pub fn record(&self, record: &AccessRecord) -> rusqlite::Result<()> {
+        let connection = self.connection.lock().unwrap();
+
+        connection.execute(
+            "INSERT INTO access_log (timestamp, work_id, cache_hit, response_time_ms, user_agent_hash, author_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.timestamp,
+                record.work_id as i64,
+                record.cache_hit,
+                record.response_time_ms as i64,
+                record.user_agent_hash,
+                record.author_hash,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// The most recent `limit` records, optionally only for one work.
+    pub fn recent(&self, work_id: Option<u64>, limit: u32) -> rusqlite::Result<Vec<AccessRecord>> {
+        let connection = self.connection.lock().unwrap();
+
+        let mut statement = connection.prepare(
+            "SELECT timestamp, work_id, cache_hit, response_time_ms, user_agent_hash, author_hash FROM access_log
+            WHERE ?1 IS NULL OR work_id = ?1
+            ORDER BY timestamp DESC
+            LIMIT ?2",
+        )?;
+
+        let records = statement.query_map(params![work_id.map(|id| id as i64), limit], |row| {
+            Ok(AccessRecord {
+                timestamp: row.get(0)?,
+                work_id: row.get::<_, i64>(1)? as u64,
+                cache_hit: row.get(2)?,
+                response_time_ms: row.get::<_, i64>(3)? as u64,
+                user_agent_hash: row.get(4)?,
+                author_hash: row.get(5)?,
+            })
+        })?;
+
+        records.collect()
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use moka::Expiry;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::meta::WorkMetadata;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    pub metadata: WorkMetadata,
+    pub fetched_at: SystemTime,
+    pub content_hash: u64,
+    /// AO3's `ETag` for the page this was scraped from, when known.
+    pub etag: Option<String>,
+}
+
+impl CacheEntry {
+    pub fn new(metadata: WorkMetadata) -> Self {
+        CacheEntry::with_fetched_at(metadata, SystemTime::now())
+    }
+
+    pub fn with_fetched_at(metadata: WorkMetadata, fetched_at: SystemTime) -> Self {
+        CacheEntry {
+            content_hash: metadata.content_hash(),
+            metadata,
+            fetched_at,
+            etag: None,
+        }
+    }
+
+    /// How much of the work's [`WorkMetadata::recommended_ttl`] is left,
+    /// counted from when it was fetched rather than when it was cached.
+    pub fn remaining_ttl(&self) -> Duration {
+        let age = SystemTime::now().duration_since(self.fetched_at).unwrap_or_default();
+
+        self.metadata.recommended_ttl().saturating_sub(age)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining_ttl().is_zero()
+    }
+}
+
+/// Expires each cached work once its [`WorkMetadata::recommended_ttl`] has
+/// passed since it was fetched, so imported entries keep their age.
+pub struct WorkExpiry;
+
+impl Expiry<u64, CacheEntry> for WorkExpiry {
+    fn expire_after_create(&self, _: &u64, entry: &CacheEntry, _: Instant) -> Option<Duration> {
+        Some(entry.remaining_ttl())
+    }
+
+    fn expire_after_update(&self, _: &u64, entry: &CacheEntry, _: Instant, _: Option<Duration>) -> Option<Duration> {
+        Some(entry.remaining_ttl())
+    }
+}
+
+/// One line of a `/cache/export` NDJSON dump.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheRecord {
+    pub work_id: u64,
+    pub metadata: WorkMetadata,
+    #[serde(with = "time::serde::rfc3339")]
+    pub fetched_at: OffsetDateTime,
+}
+
+impl From<CacheEntry> for CacheRecord {
+    fn from(entry: CacheEntry) -> Self {
+        CacheRecord {
+            work_id: entry.metadata.id,
+            metadata: entry.metadata,
+            fetched_at: entry.fetched_at.into(),
+        }
+    }
+}
+
+impl From<CacheRecord> for CacheEntry {
+    fn from(record: CacheRecord) -> Self {
+        CacheEntry::with_fetched_at(record.metadata, record.fetched_at.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ao3::meta::fixture_work;
+
+    use super::*;
+
+    #[test]
+    fn fresh_entries_get_the_full_ttl() {
+        let work = fixture_work();
+        let ttl = work.recommended_ttl();
+        let remaining = CacheEntry::new(work).remaining_ttl();
+
+        assert!(remaining <= ttl && remaining > ttl - Duration::from_secs(60));
+    }
+
+    #[test]
+    fn old_entries_count_their_age() {
+        let work = fixture_work();
+        let ttl = work.recommended_ttl();
+
+        let half_way = CacheEntry::with_fetched_at(work.clone(), SystemTime::now() - ttl / 2);
+        assert!(half_way.remaining_ttl() <= ttl / 2);
+        assert!(!half_way.is_expired());
+
+        let stale = CacheEntry::with_fetched_at(work, SystemTime::now() - ttl * 2);
+        assert!(stale.is_expired());
+    }
+}
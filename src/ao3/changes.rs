@@ -0,0 +1,113 @@
+use std::time::SystemTime;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::meta::WorkMetadata;
+
+const CHANGELOG_CAPACITY: usize = 50;
+
+/// A single field that differs between two scrapes of a work.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MetadataChange {
+    Scalar {
+        field: &'static str,
+        old: Value,
+        new: Value,
+    },
+    Tags {
+        field: &'static str,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+fn scalar<T: Serialize + PartialEq>(changes: &mut Vec<MetadataChange>, field: &'static str, old: &T, new: &T) {
+    if old != new {
+        changes.push(MetadataChange::Scalar {
+            field,
+            old: serde_json::to_value(old).unwrap_or_default(),
+            new: serde_json::to_value(new).unwrap_or_default(),
+        });
+    }
+}
+
+fn tags(changes: &mut Vec<MetadataChange>, field: &'static str, old: &[String], new: &[String]) {
+    let added = new.iter().filter(|tag| !old.contains(tag)).cloned().collect::<Vec<String>>();
+    let removed = old.iter().filter(|tag| !new.contains(tag)).cloned().collect::<Vec<String>>();
+
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(MetadataChange::Tags { field, added, removed });
+    }
+}
+
+impl WorkMetadata {
+    /// Lists every field that changed going from `self` to `other`, which are
+    /// two scrapes of the same work so the ID is not compared. Tag lists are
+    /// compared as sets.
+    pub fn diff(&self, other: &WorkMetadata) -> Vec<MetadataChange> {
+        let mut changes = Vec::new();
+
+        scalar(&mut changes, "title", &self.title, &other.title);
+        scalar(&mut changes, "author", &self.author, &other.author);
+        scalar(&mut changes, "author_link", &self.author_link, &other.author_link);
+        scalar(&mut changes, "account_name", &self.account_name, &other.account_name);
+        scalar(&mut changes, "pseudo", &self.pseudo, &other.pseudo);
+        scalar(&mut changes, "is_orphaned", &self.is_orphaned, &other.is_orphaned);
+        scalar(&mut changes, "published_date", &self.published_date, &other.published_date);
+        scalar(&mut changes, "revised_at", &self.revised_at, &other.revised_at);
+        scalar(&mut changes, "original_work_id", &self.original_work_id, &other.original_work_id);
+        scalar(&mut changes, "rating", &self.rating, &other.rating);
+        scalar(&mut changes, "language", &self.language, &other.language);
+        scalar(&mut changes, "language_code", &self.language_code, &other.language_code);
+        scalar(&mut changes, "words", &self.words, &other.words);
+        scalar(&mut changes, "chapter", &self.chapter, &other.chapter);
+        scalar(&mut changes, "total_chapters", &self.total_chapters, &other.total_chapters);
+        scalar(&mut changes, "kudos", &self.kudos, &other.kudos);
+        scalar(&mut changes, "hits", &self.hits, &other.hits);
+        scalar(&mut changes, "bookmarks", &self.bookmarks, &other.bookmarks);
+        scalar(&mut changes, "comments", &self.comments, &other.comments);
+        scalar(&mut changes, "summary", &self.summary, &other.summary);
+
+        tags(&mut changes, "fandoms", &self.fandoms, &other.fandoms);
+        tags(&mut changes, "warnings", &self.warnings, &other.warnings);
+        tags(&mut changes, "relationships", &self.relationships, &other.relationships);
+        tags(&mut changes, "characters", &self.characters, &other.characters);
+        tags(&mut changes, "tags", &self.tags, &other.tags);
+        scalar(&mut changes, "series", &self.series, &other.series);
+        scalar(&mut changes, "inspired_by", &self.inspired_by, &other.inspired_by);
+        scalar(&mut changes, "relationship_categories", &self.relationship_categories, &other.relationship_categories);
+        scalar(&mut changes, "chapters_index", &self.chapters_index, &other.chapters_index);
+        scalar(&mut changes, "chapters_list", &self.chapters_list, &other.chapters_list);
+
+        changes
+    }
+}
+
+/// The most recent changes observed for a work, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkChangelog {
+    pub work_id: u64,
+    pub changes: Vec<(SystemTime, MetadataChange)>,
+}
+
+impl WorkChangelog {
+    pub fn new(work_id: u64) -> Self {
+        WorkChangelog {
+            work_id,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, changes: Vec<MetadataChange>) {
+        let now = SystemTime::now();
+
+        self.changes.extend(changes.into_iter().map(|change| (now, change)));
+
+        if self.changes.len() > CHANGELOG_CAPACITY {
+            let excess = self.changes.len() - CHANGELOG_CAPACITY;
+            self.changes.drain(..excess);
+        }
+    }
+}
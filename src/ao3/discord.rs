@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+use super::meta::{WorkMetadata, format_thousands, truncate_chars};
+
+const AO3_RED: u32 = 0x990000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+impl EmbedField {
+    fn new(name: &str, value: String, inline: bool) -> Self {
+        EmbedField {
+            name: name.to_owned(),
+            value,
+            inline,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscordEmbedAuthor {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscordEmbedFooter {
+    pub text: String,
+}
+
+/// A Discord webhook embed object.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscordEmbed {
+    pub title: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub color: u32,
+    pub author: DiscordEmbedAuthor,
+    pub fields: Vec<EmbedField>,
+    pub footer: DiscordEmbedFooter,
+}
+
+impl WorkMetadata {
+    pub fn to_embed_fields(&self) -> Vec<EmbedField> {
+        let chapters = match self.latest_chapter_url() {
+            Some(url) => format!("[{}]({})", self.chapters_display(), url),
+            None => self.chapters_display(),
+        };
+
+        let mut fields = vec![
+            EmbedField::new("Words", format_thousands(self.words), true),
+            EmbedField::new("Chapters", chapters, true),
+            EmbedField::new("Rating", self.rating.to_string(), true),
+        ];
+
+        if !self.fandoms.is_empty() {
+            fields.push(EmbedField::new("Fandoms", truncate_chars(&self.fandoms.join(", "), 1024), false));
+        }
+
+        if let Some(language) = &self.language {
+            fields.push(EmbedField::new("Language", language.clone(), true));
+        }
+
+        fields.push(EmbedField::new("Published", self.published_date.clone(), true));
+
+        fields
+    }
+
+    pub fn to_discord_embed(&self) -> DiscordEmbed {
+        DiscordEmbed {
+            title: truncate_chars(&self.title, 256),
+            url: self.ao3_url(),
+            description: self.summary_text().map(|summary| truncate_chars(&summary, 2048)),
+            color: AO3_RED,
+            author: DiscordEmbedAuthor {
+                name: truncate_chars(self.author_display(), 256),
+                url: self.author_url().unwrap_or_else(|_| self.ao3_url()),
+            },
+            fields: self.to_embed_fields(),
+            footer: DiscordEmbedFooter {
+                text: String::from("Archive of Our Own"),
+            },
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use super::meta::{WorkMetadata, escape_xml, format_thousands, truncate_chars, wrap_words};
+
+const MASTODON_STATUS_LIMIT: usize = 500;
+const NFO_WIDTH: usize = 78;
+const PLAIN_TEXT_LIMIT: usize = 400;
+
+fn hashtag(s: &str) -> String {
+    s.chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+/// Stops forum software from interpreting user supplied text as BBCode.
+fn escape_bbcode(s: &str) -> String {
+    if s.contains(['[', ']']) {
+        format!("[NOPARSE]{}[/NOPARSE]", s.replace("[/NOPARSE]", "[/NOPARSE][NOPARSE]"))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn escape_bibtex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '{' | '}' | '%' | '&' | '#' | '$' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+impl WorkMetadata {
+    /// A single line for platforms without rich embeds, such as IRC. Tags are
+    /// dropped from the end to keep it within 400 characters.
+    pub fn to_plain_text(&self) -> String {
+        let line = |tags: &[String]| {
+            let tags = if tags.is_empty() {
+                String::new()
+            } else {
+                format!(" | Tags: {}", tags.join(", "))
+            };
+
+            format!(
+                "{} by {} | {} | {} words, {} chapters ({}){} | {}",
+                self.title,
+                self.author_display(),
+                self.fandoms.join(", "),
+                format_thousands(self.words),
+                self.chapters_display(),
+                self.rating,
+                tags,
+                self.ao3_url(),
+            )
+        };
+
+        let tag_count = (0..=self.tags.len())
+            .rev()
+            .find(|&count| line(&self.tags[..count]).chars().count() <= PLAIN_TEXT_LIMIT);
+
+        match tag_count {
+            Some(count) => line(&self.tags[..count]),
+            None => truncate_chars(&line(&[]), PLAIN_TEXT_LIMIT),
+        }
+    }
+
+    /// A BibTeX `@misc` entry citing this work.
+    pub fn to_bibtex(&self) -> String {
+        let status = if self.is_complete() { "Complete" } else { "In progress" };
+
+        let mut fields = vec![
+            format!("  title = {{{}}}", escape_bibtex(&self.title)),
+            format!("  author = {{{}}}", escape_bibtex(&self.author)),
+        ];
+
+        if let Some(year) = self.published_year() {
+            fields.push(format!("  year = {{{}}}", year));
+        }
+
+        fields.push(format!("  url = {{{}}}", self.ao3_url()));
+        fields.push(String::from("  howpublished = {Archive of Our Own}"));
+        fields.push(format!(
+            "  note = {{Words: {}, Chapters: {}, {}}}",
+            self.words,
+            self.chapters_display(),
+            status,
+        ));
+
+        format!("@misc{{ao3:{},\n{}\n}}\n", self.id, fields.join(",\n"))
+    }
+
+    /// A status ready to post to Mastodon. Warnings and then the fandom
+    /// hashtag are shortened to keep it within 500 characters, and the link
+    /// to the work is always kept whole.
+    pub fn to_mastodon_status(&self) -> String {
+        let emoji = if self.is_podfic() { "🎙️" } else { "📖" };
+        let link = format!("\n\n{}", self.ao3_url());
+
+        let text = |warnings: &str, hashtags: &str| {
+            let mut status = format!(
+                "{} {}\nby {}\n\n{} · {} words · {}\n",
+                emoji,
+                self.title,
+                self.author_display(),
+                self.fandoms.join(", "),
+                format_thousands(self.words),
+                self.chapters_display(),
+            );
+
+            if !warnings.is_empty() {
+                status.push_str(&format!("⚠️ {}\n", warnings));
+            }
+
+            status.push_str(hashtags);
+            status
+        };
+        let status = |warnings: &str, hashtags: &str| format!("{}{}", text(warnings, hashtags), link);
+
+        let warnings = self.warnings.join(", ");
+        let mut hashtags = String::from("#fanfiction");
+
+        if let Some(fandom) = self.fandoms.first().map(|fandom| hashtag(fandom)).filter(|tag| !tag.is_empty()) {
+            hashtags.push_str(&format!(" #{}", fandom));
+        }
+
+        let full = status(&warnings, &hashtags);
+        let overflow = full.chars().count().saturating_sub(MASTODON_STATUS_LIMIT);
+
+        if overflow == 0 {
+            return full;
+        }
+
+        let warnings_length = warnings.chars().count();
+
+        if overflow < warnings_length {
+            return status(&truncate_chars(&warnings, warnings_length - overflow), &hashtags);
+        }
+
+        let without_warnings = status("", &hashtags);
+
+        if without_warnings.chars().count() <= MASTODON_STATUS_LIMIT {
+            return without_warnings;
+        }
+
+        let text_limit = MASTODON_STATUS_LIMIT.saturating_sub(link.chars().count());
+
+        format!("{}{}", truncate_chars(&text("", "#fanfiction"), text_limit), link)
+    }
+
+    /// A forum post linking the work, for SpaceBattles style BBCode forums.
+    pub fn to_bbcode(&self) -> String {
+        let author_url = self.author_url().unwrap_or_else(|_| self.ao3_url());
+
+        format!(
+            "[B][URL={}]{}[/URL][/B] by [URL={}]{}[/URL]\n[LIST]\n[*]Words: {}\n[*]Chapters: {}\n[/LIST]\n[QUOTE]{}[/QUOTE]",
+            self.ao3_url(),
+            escape_bbcode(&self.title),
+            author_url,
+            escape_bbcode(self.author_display()),
+            format_thousands(self.words),
+            self.chapters_display(),
+            escape_bbcode(&self.summary_text().unwrap_or_default()),
+        )
+    }
+
+    /// A plain two column `<table>` for pasting into wiki pages.
+    pub fn to_html_table(&self) -> String {
+        let rows = [
+            ("Title", format!("<a href=\"{}\">{}</a>", self.ao3_url(), escape_xml(&self.title))),
+            ("Author", escape_xml(self.author_display())),
+            ("Fandom", escape_xml(&self.fandoms.join(", "))),
+            ("Rating", escape_xml(&self.rating.to_string())),
+            ("Words", format_thousands(self.words)),
+            ("Chapters", escape_xml(&self.chapters_display())),
+            ("Tags", escape_xml(&self.tags.join(", "))),
+        ];
+
+        let rows: String = rows.iter()
+            .map(|(field, value)| format!("<tr><td>{}</td><td>{}</td></tr>\n", field, value))
+            .collect();
+
+        format!("<table>\n{}</table>\n", rows)
+    }
+
+    /// A scene release style `.nfo` file: a boxed header followed by the
+    /// work's fields, wrapped to 78 columns.
+    pub fn to_nfo(&self) -> String {
+        let border = format!("+{}+\n", "-".repeat(NFO_WIDTH - 2));
+        let boxed = |text: &str| format!("| {:^width$} |\n", text, width = NFO_WIDTH - 4);
+
+        let mut nfo = border.clone();
+        nfo.push_str(&boxed("ARCHIVE OF OUR OWN"));
+        for line in wrap_words(&self.title, NFO_WIDTH - 4) {
+            nfo.push_str(&boxed(&line));
+        }
+        nfo.push_str(&border);
+        nfo.push('\n');
+
+        let fields = [
+            ("Title", self.title.clone()),
+            ("Author", self.author_display().to_owned()),
+            ("Fandom", self.fandoms.join(", ")),
+            ("Rating", self.rating.to_string()),
+            ("Words", format_thousands(self.words)),
+            ("Chapters", self.chapters_display()),
+            ("Summary", self.summary_text().unwrap_or_default()),
+            ("Tags", self.tags.join(", ")),
+            ("URL", self.ao3_url()),
+        ];
+
+        for (field, value) in fields {
+            let mut lines = wrap_words(&value, NFO_WIDTH - 12).into_iter();
+
+            nfo.push_str(&format!("  {:<8}: {}\n", field, lines.next().unwrap_or_default()));
+            for line in lines {
+                nfo.push_str(&format!("  {:<8}  {}\n", "", line));
+            }
+        }
+
+        nfo
+    }
+
+    /// An OPF package document holding the work's Dublin Core metadata, as
+    /// read by Calibre and other e-book managers.
+    pub fn to_opf(&self) -> String {
+        let mut metadata = vec![
+            format!("<dc:identifier id=\"ao3-id\">{}</dc:identifier>", self.ao3_url()),
+            format!("<dc:title>{}</dc:title>", escape_xml(&self.title)),
+            format!("<dc:creator>{}</dc:creator>", escape_xml(self.author_display())),
+        ];
+
+        for subject in self.fandoms.iter().chain(self.tags.iter()) {
+            metadata.push(format!("<dc:subject>{}</dc:subject>", escape_xml(subject)));
+        }
+
+        if let Some(summary) = self.summary_text() {
+            metadata.push(format!("<dc:description>{}</dc:description>", escape_xml(&summary)));
+        }
+
+        metadata.push(String::from("<dc:publisher>Archive of Our Own</dc:publisher>"));
+        metadata.push(format!("<dc:date>{}</dc:date>", escape_xml(&self.published_date)));
+        metadata.push(format!("<dc:language>{}</dc:language>", self.language_code.as_deref().unwrap_or("und")));
+
+        let mut opf = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"2.0\" unique-identifier=\"ao3-id\">\n",
+            "  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:opf=\"http://www.idpf.org/2007/opf\">\n",
+        ));
+
+        for element in metadata {
+            opf.push_str(&format!("    {}\n", element));
+        }
+
+        opf.push_str("  </metadata>\n</package>\n");
+        opf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ao3::meta::fixture_work;
+
+    use super::*;
+
+    fn ampersand_work() -> WorkMetadata {
+        let mut work = fixture_work();
+        work.title = String::from("Tea & <Sympathy>");
+        work
+    }
+
+    #[test]
+    fn scraped_entities_are_decoded() {
+        assert!(fixture_work().relationships.contains(&String::from("alice & carol")));
+    }
+
+    #[test]
+    fn text_formats_use_decoded_text() {
+        let work = ampersand_work();
+
+        assert!(work.to_plain_text().starts_with("Tea & <Sympathy> by"));
+        assert!(work.to_mastodon_status().starts_with("📖 Tea & <Sympathy>\n"));
+        assert!(work.to_bbcode().contains("]Tea & <Sympathy>[/URL]"));
+        assert!(work.to_nfo().contains("Title   : Tea & <Sympathy>\n"));
+        assert!(work.to_bibtex().contains("title = {Tea \\& <Sympathy>}"));
+    }
+
+    #[test]
+    fn markup_formats_escape_once() {
+        let work = ampersand_work();
+
+        for markup in [work.to_html_table(), work.to_opf()] {
+            assert!(markup.contains("Tea &amp; &lt;Sympathy&gt;"), "{}", markup);
+            assert!(!markup.contains("&amp;amp;"), "{}", markup);
+        }
+    }
+
+    #[test]
+    fn mastodon_status_keeps_short_works_whole() {
+        let status = fixture_work().to_mastodon_status();
+
+        assert!(status.starts_with("📖 The Long Way Round\nby PenName (MainAccount)\n"));
+        assert!(status.ends_with("#fanfiction #originalwork\n\nhttps://archiveofourown.org/works/123456"));
+    }
+
+    #[test]
+    fn mastodon_status_never_cuts_the_link() {
+        let mut work = fixture_work();
+        work.title = "A".repeat(600);
+
+        let status = work.to_mastodon_status();
+
+        assert_eq!(status.chars().count(), MASTODON_STATUS_LIMIT);
+        assert!(status.ends_with("…\n\nhttps://archiveofourown.org/works/123456"));
+    }
+
+    #[test]
+    fn bbcode_leaves_plain_titles_alone() {
+        let bbcode = fixture_work().to_bbcode();
+
+        assert!(bbcode.starts_with("[B][URL=https://archiveofourown.org/works/123456]The Long Way Round[/URL][/B]"));
+    }
+
+    #[test]
+    fn bbcode_wraps_titles_with_tags_in_noparse() {
+        let mut work = fixture_work();
+        work.title = String::from("[b]Bold[/b] [URL=https://example.com]Move[/URL]");
+
+        assert!(work.to_bbcode().contains("[URL=https://archiveofourown.org/works/123456][NOPARSE][b]Bold[/b] [URL=https://example.com]Move[/URL][/NOPARSE][/URL]"));
+    }
+
+    #[test]
+    fn bbcode_cannot_close_noparse_early() {
+        let mut work = fixture_work();
+        work.title = String::from("[/NOPARSE][IMG]x[/IMG]");
+
+        assert_eq!(escape_bbcode(&work.title), "[NOPARSE][/NOPARSE][NOPARSE][IMG]x[/IMG][/NOPARSE]");
+        assert!(work.to_bbcode().contains("[NOPARSE][/NOPARSE][NOPARSE][IMG]x[/IMG][/NOPARSE]"));
+    }
+
+    #[test]
+    fn bbcode_escapes_author() {
+        let mut work = fixture_work();
+        work.author = String::from("[i]Author[/i]");
+
+        assert!(work.to_bbcode().contains("[NOPARSE][i]Author[/i][/NOPARSE][/URL]"));
+    }
+}
@@ -0,0 +1,40 @@
+use dashmap::DashMap;
+use itertools::Itertools;
+
+use super::meta::WorkMetadata;
+
+/// How many cached works have been seen for each fandom.
+#[derive(Debug, Default)]
+pub struct FandomIndex {
+    counts: DashMap<String, u64>,
+}
+
+impl FandomIndex {
+    pub fn record(&self, work: &WorkMetadata) {
+        for fandom in &work.fandoms {
+            *self.counts.entry(fandom.clone()).or_default() += 1;
+        }
+    }
+
+    /// The `limit` most seen fandoms, most seen first.
+    pub fn top(&self, limit: usize) -> Vec<(String, u64)> {
+        self.counts.iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .sorted_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)))
+            .take(limit)
+            .collect()
+    }
+
+    /// 1 for the most seen fandom, fandoms with the same count share a rank.
+    pub fn rank(&self, fandom: &str) -> Option<u64> {
+        let count = *self.counts.get(fandom)?;
+
+        Some(self.counts.iter().filter(|entry| *entry.value() > count).count() as u64 + 1)
+    }
+}
+
+impl WorkMetadata {
+    pub fn primary_fandom_rank(&self, index: &FandomIndex) -> Option<u64> {
+        index.rank(self.fandoms.first()?)
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::SystemTime;
+
+use askama::Template;
+use time::{OffsetDateTime, format_description::well_known::{Rfc2822, Rfc3339}};
+
+use super::{cache::CacheEntry, meta::escape_xml};
+
+fn rfc3339(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+fn rfc2822(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(&Rfc2822)
+        .unwrap_or_default()
+}
+
+/// An RSS 2.0 `<channel>` of recently cached works.
+#[derive(Debug, Template)]
+#[template(path = "feed.rss", escape = "html")]
+pub struct RssTemplate {
+    pub host: String,
+    pub items: Vec<RssItem>,
+}
+
+#[derive(Debug)]
+pub struct RssItem {
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    pub author: String,
+    pub pub_date: String,
+    pub categories: Vec<String>,
+}
+
+impl From<&CacheEntry> for RssItem {
+    fn from(entry: &CacheEntry) -> Self {
+        let work = &entry.metadata;
+
+        RssItem {
+            title: work.title.clone(),
+            link: work.ao3_url(),
+            description: work.summary_text().unwrap_or_default(),
+            author: format!("noreply@archiveofourown.org ({})", work.author_display()),
+            pub_date: rfc2822(entry.fetched_at),
+            categories: work.fandoms.clone(),
+        }
+    }
+}
+
+impl RssTemplate {
+    pub fn new(host: &str, entries: &[CacheEntry]) -> Self {
+        RssTemplate {
+            host: host.to_owned(),
+            items: entries.iter().map(RssItem::from).collect(),
+        }
+    }
+}
+
+/// Wraps the given cache entries in an Atom 1.0 `<feed>` document.
+pub fn atom_feed(host: &str, entries: &[CacheEntry]) -> String {
+    let updated = entries.iter()
+        .map(|entry| entry.fetched_at)
+        .max()
+        .unwrap_or_else(SystemTime::now);
+
+    let body: String = entries.iter()
+        .filter_map(|entry| entry.metadata.to_atom_entry())
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><feed xmlns=\"http://www.w3.org/2005/Atom\"><title>ao3.nl</title><id>{host}/feed.atom</id><link rel=\"self\" href=\"{host}/feed.atom\"/><updated>{}</updated>{}</feed>",
+        rfc3339(updated),
+        body,
+        host = escape_xml(host),
+    )
+}
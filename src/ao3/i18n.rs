@@ -0,0 +1,52 @@
+use phf::phf_map;
+
+/// Labels used in the oEmbed text for a single display language.
+#[derive(Debug)]
+pub struct Translations {
+    pub words: &'static str,
+    pub chapters: &'static str,
+    pub comments: &'static str,
+}
+
+const ENGLISH: Translations = Translations {
+    words: "words",
+    chapters: "chapters",
+    comments: "comments",
+};
+
+static TRANSLATIONS: phf::Map<&'static str, Translations> = phf_map! {
+    "en" => ENGLISH,
+    "ja" => Translations {
+        words: "語",
+        chapters: "章",
+        comments: "コメント",
+    },
+    "zh" => Translations {
+        words: "字",
+        chapters: "章",
+        comments: "评论",
+    },
+    "es" => Translations {
+        words: "palabras",
+        chapters: "capítulos",
+        comments: "comentarios",
+    },
+    "fr" => Translations {
+        words: "mots",
+        chapters: "chapitres",
+        comments: "commentaires",
+    },
+    "de" => Translations {
+        words: "Wörter",
+        chapters: "Kapitel",
+        comments: "Kommentare",
+    },
+};
+
+/// Looks up the translations for `lang`, accepting region suffixes such as
+/// `es-MX` and falling back to English for unknown languages.
+pub fn get_translations(lang: &str) -> &'static Translations {
+    let lang = lang.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+
+    TRANSLATIONS.get(lang.as_str()).unwrap_or(&ENGLISH)
+}
@@ -0,0 +1,69 @@
+use phf::phf_map;
+
+/// AO3's language display names mapped to their BCP-47 codes.
+static LANGUAGE_CODES: phf::Map<&'static str, &'static str> = phf_map! {
+    "English" => "en",
+    "中文-普通话 國語" => "zh",
+    "中文-广东话 粵語" => "yue",
+    "日本語" => "ja",
+    "한국어" => "ko",
+    "Español" => "es",
+    "Français" => "fr",
+    "Deutsch" => "de",
+    "Italiano" => "it",
+    "Português brasileiro" => "pt-BR",
+    "Português europeu" => "pt-PT",
+    "Русский" => "ru",
+    "Polski" => "pl",
+    "Nederlands" => "nl",
+    "Svenska" => "sv",
+    "Norsk" => "no",
+    "Dansk" => "da",
+    "Suomi" => "fi",
+    "Čeština" => "cs",
+    "Magyar" => "hu",
+    "Română" => "ro",
+    "Türkçe" => "tr",
+    "Українська" => "uk",
+    "Ελληνικά" => "el",
+    "עברית" => "he",
+    "العربية" => "ar",
+    "فارسی" => "fa",
+    "Bahasa Indonesia" => "id",
+    "Tiếng Việt" => "vi",
+    "ไทย" => "th",
+    "Català" => "ca",
+    "Esperanto" => "eo",
+    "Latinum" => "la",
+};
+
+/// Looks up the BCP-47 code for a language as AO3 displays it.
+pub fn language_code(display_name: &str) -> Option<&'static str> {
+    LANGUAGE_CODES.get(display_name.trim()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_known_languages() {
+        assert_eq!(language_code("English"), Some("en"));
+        assert_eq!(language_code("日本語"), Some("ja"));
+        assert_eq!(language_code("Deutsch"), Some("de"));
+        assert_eq!(language_code("Português brasileiro"), Some("pt-BR"));
+        assert_eq!(language_code("中文-普通话 國語"), Some("zh"));
+    }
+
+    #[test]
+    fn trims_display_names() {
+        assert_eq!(language_code("  Français\n"), Some("fr"));
+    }
+
+    #[test]
+    fn unknown_languages_have_no_code() {
+        assert_eq!(language_code("Klingon"), None);
+        assert_eq!(language_code("english"), None);
+        assert_eq!(language_code(""), None);
+    }
+}
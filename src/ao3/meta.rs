@@ -1,18 +1,35 @@
-use std::{string::FromUtf8Error, env};
+use std::{string::FromUtf8Error, str::FromStr, fmt, env, sync::Arc, time::Duration};
 
 use lazy_static::lazy_static;
 use minify_html::{Cfg, minify};
 use scraper::{Selector, Html, ElementRef};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, format_description::well_known::Rfc3339, macros::format_description};
+use xxhash_rust::xxh3::xxh3_64;
 use askama::Template;
+use futures::{Stream, StreamExt, stream};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use axum::http::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH, HeaderMap, HeaderValue};
 
 use itertools::Itertools;
 
+use crate::env_flag;
+
+use super::{languages::language_code, platform::Platform, relationships::RelationshipCategory, session::CookieJar, urls::{AO3_BASE_URL, ao3_work_url, ao3_user_url, ao3_chapter_url, ao3_series_url, ao3_tag_url, host}};
+
 use nom::{
-    IResult, bytes, combinator::{opt, map_res, recognize}, character::complete::digit1
+    IResult, bytes, branch::alt, combinator::{opt, map_res, recognize}, character::complete::{digit1, char},
+    multi::many1, sequence::{pair, preceded}
 };
 
+/// Minimum gap between requests in `WorkMetadata::from_id_range_stream`.
+pub const BULK_SCRAPE_DELAY: Duration = Duration::from_millis(500);
+
 lazy_static! {
     static ref WORK: Selector = Selector::parse(".work").unwrap();
     static ref WORK_HEADER: Selector = Selector::parse("div.header.module").unwrap();
@@ -21,6 +38,7 @@ lazy_static! {
     static ref FANDOMS: Selector = Selector::parse(".fandoms").unwrap();
     static ref DATE: Selector = Selector::parse(".datetime").unwrap();
     static ref TAG: Selector = Selector::parse(".tag").unwrap();
+    static ref RATING: Selector = Selector::parse(".required-tags .rating").unwrap();
     static ref WARNINGS: Selector = Selector::parse(".warnings>strong>a").unwrap();
     static ref RELATIONSHIPS: Selector = Selector::parse(".relationships>a").unwrap();
     static ref CHARACTERS: Selector = Selector::parse(".characters>a").unwrap();
@@ -31,26 +49,242 @@ lazy_static! {
     static ref CHAPTERS: Selector = Selector::parse("dd.chapters").unwrap();
     static ref KUDOS: Selector = Selector::parse("dd.kudos>a").unwrap();
     static ref HITS: Selector = Selector::parse("dd.hits").unwrap();
+    static ref BOOKMARKS: Selector = Selector::parse("dd.bookmarks").unwrap();
+    static ref COMMENTS: Selector = Selector::parse("dd.comments").unwrap();
+    static ref STATUS: Selector = Selector::parse("dd.status").unwrap();
     static ref SUMMARY: Selector = Selector::parse("blockquote.summary").unwrap();
+    static ref CHAPTER_TEXT: Selector = Selector::parse("#chapters .userstuff").unwrap();
+    static ref BLURB: Selector = Selector::parse("li.work.blurb").unwrap();
+    static ref BLURB_WORK_LINK: Selector = Selector::parse("li.work.blurb h4 a[href^=\"/works/\"]").unwrap();
+    static ref WORK_META: Selector = Selector::parse("dl.work").unwrap();
+    static ref LOGIN: Selector = Selector::parse("#main.sessions-new, form#new_user").unwrap();
+    static ref CHAPTER_INDEX: Selector = Selector::parse("#chapter_index select option").unwrap();
+    static ref NAVIGATE_CHAPTERS: Selector = Selector::parse("ol.chapter.index li").unwrap();
+    static ref NAVIGATE_LINK: Selector = Selector::parse("a").unwrap();
+    static ref NAVIGATE_DATE: Selector = Selector::parse("span.datetime").unwrap();
+    static ref SERIES: Selector = Selector::parse("ul.series li a[href^=\"/series/\"]").unwrap();
+    static ref INSPIRED_BY: Selector = Selector::parse("dd.inspiration a[href^=\"/works/\"]").unwrap();
+    static ref ORIGINAL_WORK: Selector = Selector::parse("dl.work dd.parent-work a[href^=\"/works/\"]").unwrap();
+    static ref KUDOS_USERS: Selector = Selector::parse("p#kudos a[href^=\"/users/\"]").unwrap();
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Forces every selector to compile so that a typo panics at startup rather
+/// than on the first request that happens to use it.
+pub fn validate_selectors() {
+    let selectors: &[(&str, &Selector)] = &[
+        ("WORK", &*WORK),
+        ("WORK_HEADER", &*WORK_HEADER),
+        ("TITLE", &*TITLE),
+        ("AUTHOR", &*AUTHOR),
+        ("FANDOMS", &*FANDOMS),
+        ("DATE", &*DATE),
+        ("TAG", &*TAG),
+        ("RATING", &*RATING),
+        ("WARNINGS", &*WARNINGS),
+        ("RELATIONSHIPS", &*RELATIONSHIPS),
+        ("CHARACTERS", &*CHARACTERS),
+        ("TAGS", &*TAGS),
+        ("STATS", &*STATS),
+        ("LANGUAGE", &*LANGUAGE),
+        ("WORDS", &*WORDS),
+        ("CHAPTERS", &*CHAPTERS),
+        ("KUDOS", &*KUDOS),
+        ("HITS", &*HITS),
+        ("BOOKMARKS", &*BOOKMARKS),
+        ("COMMENTS", &*COMMENTS),
+        ("STATUS", &*STATUS),
+        ("SUMMARY", &*SUMMARY),
+        ("CHAPTER_TEXT", &*CHAPTER_TEXT),
+        ("BLURB", &*BLURB),
+        ("BLURB_WORK_LINK", &*BLURB_WORK_LINK),
+        ("WORK_META", &*WORK_META),
+        ("LOGIN", &*LOGIN),
+        ("CHAPTER_INDEX", &*CHAPTER_INDEX),
+        ("NAVIGATE_CHAPTERS", &*NAVIGATE_CHAPTERS),
+        ("NAVIGATE_LINK", &*NAVIGATE_LINK),
+        ("NAVIGATE_DATE", &*NAVIGATE_DATE),
+        ("SERIES", &*SERIES),
+        ("ORIGINAL_WORK", &*ORIGINAL_WORK),
+        ("INSPIRED_BY", &*INSPIRED_BY),
+        ("KUDOS_USERS", &*KUDOS_USERS),
+    ];
+
+    for (name, _) in selectors.iter() {
+        tracing::debug!("Compiled selector {}", name);
+    }
+
+    tracing::info!("Validated {} selectors", selectors.len());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WorkMetadata {
     pub id: u64,
     pub title: String,
     pub author: String,
+    /// Absolute URL of the author's pseud, as linked from the work.
+    pub author_link: Option<String>,
+    /// The account the author's pseud belongs to, from the `/users/` segment of `author_link`.
+    #[serde(default)]
+    pub account_name: String,
+    /// The pseud the work is posted under, from the `/pseuds/` segment of `author_link`.
+    pub pseudo: Option<String>,
+    /// Whether the author has orphaned the work to `orphan_account`.
+    pub is_orphaned: bool,
     pub published_date: String,
+    /// The date AO3 shows the work as last updated or completed, as `YYYY-MM-DD`.
+    pub revised_at: Option<String>,
+    pub rating: Rating,
     pub fandoms: Vec<String>,
     pub warnings: Vec<String>,
     pub relationships: Vec<String>,
     pub characters: Vec<String>,
     pub tags: Vec<String>,
     pub language: Option<String>,
+    /// BCP-47 code for `language`, when it is one AO3 commonly uses.
+    pub language_code: Option<String>,
     pub words: u64,
     pub chapter: u16,
     pub total_chapters: Option<u16>,
     pub kudos: u32,
     pub hits: u64,
+    pub bookmarks: Option<u64>,
+    pub comments: Option<u64>,
+    pub summary: Option<String>,
+    pub chapters_index: Vec<(u16, u64)>,
+    pub chapters_list: Option<Vec<ChapterRef>>,
+    pub series: Vec<SeriesRef>,
+    /// The work this one translates, from the work's parent work link.
+    pub original_work_id: Option<u64>,
+    /// The works this one remixes or was inspired by.
+    #[serde(default)]
+    pub inspired_by: Vec<WorkRef>,
+    /// The category of each relationship, only filled in with `ENRICH_RELATIONSHIPS=true`.
+    #[serde(default)]
+    pub relationship_categories: Vec<(String, RelationshipCategory)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ChapterRef {
+    pub number: u16,
+    pub id: u64,
+    pub title: Option<String>,
+    /// The date the chapter was posted, as `YYYY-MM-DD`.
+    pub published: Option<String>,
+}
+
+/// Just enough of a work to render a first embed while the full scrape runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkMetadataMinimal {
+    pub id: u64,
+    pub title: String,
+    pub author: String,
+}
+
+/// The work page served from a [`WorkMetadataMinimal`].
+#[derive(Debug, Template)]
+#[template(path = "minimal.html")]
+pub struct MinimalTemplate {
+    pub url: String,
+    pub title: String,
+    pub author: String,
+}
+
+impl From<WorkMetadataMinimal> for MinimalTemplate {
+    fn from(work: WorkMetadataMinimal) -> Self {
+        MinimalTemplate {
+            url: ao3_work_url(work.id),
+            title: work.title,
+            author: work.author,
+        }
+    }
+}
+
+impl MinimalTemplate {
+    pub fn render_html(&self) -> Result<String, WorkError> {
+        minify_rendered(self.render()?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SeriesRef {
+    pub id: u64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkRef {
+    pub id: u64,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Rating {
+    General,
+    Teen,
+    Mature,
+    Explicit,
+    NotRated,
+}
+
+impl Rating {
+    fn from_ao3(s: &str) -> Self {
+        match s.trim() {
+            "General Audiences" => Rating::General,
+            "Teen And Up Audiences" => Rating::Teen,
+            "Mature" => Rating::Mature,
+            "Explicit" => Rating::Explicit,
+            _ => Rating::NotRated,
+        }
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rating::General => "General Audiences",
+            Rating::Teen => "Teen And Up Audiences",
+            Rating::Mature => "Mature",
+            Rating::Explicit => "Explicit",
+            Rating::NotRated => "Not Rated",
+        })
+    }
+}
+
+/// How long a work is, as fanfic readers tend to size them up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+pub enum WordCountTier {
+    Drabble,
+    Oneshot,
+    Shortfic,
+    Longfic,
+    Epic,
+    Behemoth,
+}
+
+impl WordCountTier {
+    pub fn from_words(words: u64) -> Self {
+        match words {
+            0..=999 => WordCountTier::Drabble,
+            1_000..=9_999 => WordCountTier::Oneshot,
+            10_000..=49_999 => WordCountTier::Shortfic,
+            50_000..=99_999 => WordCountTier::Longfic,
+            100_000..=499_999 => WordCountTier::Epic,
+            _ => WordCountTier::Behemoth,
+        }
+    }
+}
+
+impl fmt::Display for WordCountTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WordCountTier::Drabble => "📝 Drabble",
+            WordCountTier::Oneshot => "📄 Oneshot",
+            WordCountTier::Shortfic => "📖 Shortfic",
+            WordCountTier::Longfic => "📚 Longfic",
+            WordCountTier::Epic => "🏛️ Epic",
+            WordCountTier::Behemoth => "🌌 Behemoth",
+        })
+    }
 }
 
 fn join_quoted(strings: Vec<String>) -> String {
@@ -59,76 +293,250 @@ fn join_quoted(strings: Vec<String>) -> String {
         .collect()
 }
 
+pub(crate) fn truncate_chars(s: &str, limit: usize) -> String {
+    if s.chars().count() <= limit {
+        return s.to_owned();
+    }
+
+    let mut truncated = s.chars().take(limit.saturating_sub(1)).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Greedily wraps `s` into lines of at most `width` characters.
+pub(crate) fn wrap_words(s: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in s.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.push_str(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
 #[derive(Debug, Serialize, Template)]
 #[template(path = "work.html")]
 pub struct WorkTemplate {
     pub id: u64,
+    pub url: String,
     pub title: String,
     pub author: String,
     pub description: String,
     pub embed_url: String,
+    pub image_url: String,
+    pub json_ld: String,
+    pub subjects: Vec<String>,
+    /// `(name, url)` of each fandom and freeform tag, linked to the AO3 works listing.
+    pub fandom_links: Vec<(String, String)>,
+    pub tag_links: Vec<(String, String)>,
+    pub language: Option<String>,
+    pub locale: Option<String>,
+    pub published_date: String,
+    /// ISO-8601 date of the last update, only set when it differs from `published_date`.
+    pub updated_time: Option<String>,
 }
 
 impl Into<WorkTemplate> for WorkMetadata {
     fn into(self) -> WorkTemplate {
-        let embed_url = format!(
-            "{}/oembed/{}/{}/{}/{}/{}/{}",
-            env::var("HOST").unwrap_or_else(|_| String::from("http://localhost:3000")),
-            self.id,
-            urlencoding::encode(&self.author),
-            self.words,
-            self.chapter,
-            self.total_chapters
-                .map(|c| c.to_string())
-                .unwrap_or_else(|| String::from("?")),
-            urlencoding::encode(&self.published_date)
-        );
-
-        WorkTemplate {
-            id: self.id,
-            title: self.title,
-            author: self.author,
-            description: {
-                let warnings = join_quoted(self.warnings);
-                let characters = join_quoted(self.characters);
-                let tags = join_quoted(self.tags);
-
-                format!("{}\n{}\n{}", warnings, characters, tags)
-            },
-            embed_url,
-        }
+        self.format_for_platform(Platform::Generic)
     }
 }
 
 impl WorkTemplate {
     pub fn render_html(&self) -> Result<String, WorkError> {
-        let html = self.render()?;
+        minify_rendered(self.render()?)
+    }
+}
+
+/// The work page served to Mastodon's preview fetcher, sized for its
+/// preview cards and attributing the post to a fediverse account.
+#[derive(Debug, Template)]
+#[template(path = "mastodon.html")]
+pub struct MastodonTemplate {
+    pub work: WorkTemplate,
+    pub creator: String,
+}
+
+impl MastodonTemplate {
+    /// Uses `MASTODON_CREATOR` as the `fediverse:creator`, falling back to the official bot account.
+    pub fn new(work: WorkTemplate) -> Self {
+        MastodonTemplate {
+            work,
+            creator: env::var("MASTODON_CREATOR").unwrap_or_else(|_| "@ao3officialbot@mastodon.social".to_owned()),
+        }
+    }
+
+    pub fn render_html(&self) -> Result<String, WorkError> {
+        minify_rendered(self.render()?)
+    }
+}
+
+fn minify_rendered(html: String) -> Result<String, WorkError> {
+    let mut cfg = Cfg::new();
+    cfg.do_not_minify_doctype = true;
+    cfg.ensure_spec_compliant_unquoted_attribute_values = true;
+    cfg.keep_spaces_between_attributes = true;
+
+    let minified = minify(html.as_bytes(), &cfg);
 
-        let mut cfg = Cfg::new();
-        cfg.do_not_minify_doctype = true;
-        cfg.ensure_spec_compliant_unquoted_attribute_values = true;
-        cfg.keep_spaces_between_attributes = true;
+    Ok(String::from_utf8(minified)?)
+}
+
+/// Query parameters controlling which content AO3 shows when scraping a work.
+#[derive(Debug, Clone)]
+pub struct ScrapingOptions {
+    pub view_adult: bool,
+    pub only_for_users: bool,
+    pub view_full_work: bool,
+}
 
-        let minified = minify(html.as_bytes(), &cfg);
+impl Default for ScrapingOptions {
+    fn default() -> Self {
+        ScrapingOptions {
+            view_adult: true,
+            only_for_users: false,
+            view_full_work: false,
+        }
+    }
+}
 
-        Ok(String::from_utf8(minified)?)
+impl ScrapingOptions {
+    fn query(&self) -> Vec<(&'static str, &'static str)> {
+        [
+            ("view_adult", self.view_adult),
+            ("only_for_users", self.only_for_users),
+            ("view_full_work", self.view_full_work),
+        ]
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| (name, "true"))
+            .collect()
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AO3StructureVersion {
+    V1,
+    Unknown,
+    LoginRequired,
+}
+
+/// An invariant broken by scraped metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    #[error("the work id is zero")]
+    MissingId,
+    #[error("the title is empty")]
+    EmptyTitle,
+    #[error("the author is empty")]
+    EmptyAuthor,
+    #[error("the word count is zero")]
+    NoWords,
+    #[error("the chapter count is zero")]
+    NoChapters,
+    #[error("chapter {chapter} is past the total of {total}")]
+    ChapterPastTotal { chapter: u16, total: u16 },
+    #[error("the published date {0:?} is not YYYY-MM-DD")]
+    MalformedDate(String),
+}
+
 #[derive(Debug, Error)]
 pub enum WorkError {
     #[error("could not find the work information")]
     WorkError,
     #[error("could not parse work information")]
     ParsingError,
-    #[error("could not request the work")]
+    #[error("could not parse {field}: {source}")]
+    ParseField {
+        field: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("the work does not exist")]
+    NotFound,
+    #[error("scraped metadata is invalid: {}", .0.iter().map(ToString::to_string).join(", "))]
+    ValidationError(Vec<ValidationError>),
+    #[error("the work is restricted to logged in users")]
+    RestrictedWork,
+    #[error("the work has been orphaned")]
+    OrphanedWork,
+    #[error("rate limited by ao3")]
+    RateLimited,
+    #[error("unrecognised ao3 page structure")]
+    UnknownStructure,
+    #[error("proxy error: {0}")]
+    ProxyError(String),
+    #[error("could not request the work: {0}")]
     RequestError(#[from] reqwest::Error),
-    #[error("error filling the template")]
+    #[error("error filling the template: {0}")]
     TemplatingError(#[from] askama::Error),
-    #[error("minifying error")]
+    #[error("minifying error: {0}")]
     Minify(#[from] FromUtf8Error),
 }
 
+/// A `WorkError` along with the work it happened for.
+#[derive(Debug, Error)]
+#[error("work {work_id}: {error}")]
+pub struct ContextualWorkError {
+    pub work_id: u64,
+    #[source]
+    pub error: WorkError,
+}
+
+impl WorkError {
+    pub fn with_context(self, work_id: u64) -> ContextualWorkError {
+        ContextualWorkError { work_id, error: self }
+    }
+
+    /// Whether the page was fetched but couldn't be turned into valid metadata.
+    pub fn is_parse_failure(&self) -> bool {
+        matches!(
+            self,
+            WorkError::WorkError | WorkError::ParsingError | WorkError::ParseField { .. } | WorkError::ValidationError(_) | WorkError::UnknownStructure
+        )
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            WorkError::WorkError | WorkError::NotFound | WorkError::OrphanedWork => StatusCode::NOT_FOUND,
+            WorkError::ParsingError | WorkError::ParseField { .. } | WorkError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WorkError::RestrictedWork => StatusCode::FORBIDDEN,
+            WorkError::RateLimited => StatusCode::SERVICE_UNAVAILABLE,
+            WorkError::RequestError(_) | WorkError::UnknownStructure | WorkError::ProxyError(_) => StatusCode::BAD_GATEWAY,
+            WorkError::TemplatingError(_) | WorkError::Minify(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+
+/// Attaches the name of the field being scraped to a failed lookup or parse.
+trait Context<T> {
+    fn context(self, field: &'static str) -> Result<T, WorkError>;
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context(self, field: &'static str) -> Result<T, WorkError> {
+        self.ok_or_else(|| WorkError::ParseField { field, source: "element not found".into() })
+    }
+}
+
+impl<T, E: std::error::Error + Send + Sync + 'static> Context<T> for Result<T, E> {
+    fn context(self, field: &'static str) -> Result<T, WorkError> {
+        self.map_err(|err| WorkError::ParseField { field, source: Box::new(err) })
+    }
+}
 
 fn chapter(input: &str) -> IResult<&str, u16> {
     map_res(recognize(digit1), str::parse)(input)
@@ -142,72 +550,1487 @@ fn chapters(input: &str) -> IResult<&str, (u16, Option<u16>)> {
     Ok((input, (chapter_value, total_chapters)))
 }
 
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit()
+}
+
+/// Parses an AO3 stat count, either as plain digits or with comma separated
+/// thousands groups (`1,234,567`).
+fn count<T: FromStr>(input: &str) -> IResult<&str, T> {
+    alt((
+        map_res(
+            recognize(pair(
+                bytes::complete::take_while_m_n(1, 3, is_digit),
+                many1(preceded(char(','), bytes::complete::take_while_m_n(3, 3, is_digit))),
+            )),
+            |s: &str| s.replace(',', "").parse::<T>(),
+        ),
+        map_res(digit1, str::parse::<T>),
+    ))(input)
+}
+
+fn word_count(input: &str) -> IResult<&str, u64> {
+    count(input)
+}
+
+fn parse_count<T: FromStr>(input: &str) -> Result<T, WorkError> {
+    match count(input.trim()) {
+        Ok(("", value)) => Ok(value),
+        _ => Err(WorkError::ParsingError),
+    }
+}
+
+/// Parses the count under `selector`, treating a missing element as zero
+/// since AO3 leaves out stats like kudos until a work has any.
+fn select_count_optional<T: FromStr + Default>(stats: ElementRef, selector: &Selector, field: &'static str) -> Result<T, WorkError> {
+    match stats.select(selector).next() {
+        Some(e) => parse_count(&e.text().collect::<String>()).context(field),
+        None => Ok(T::default()),
+    }
+}
+
 impl TryFrom<(u64, ElementRef<'_>)> for WorkMetadata {
     type Error = WorkError;
 
     fn try_from((id, work): (u64, ElementRef)) -> Result<Self, Self::Error> {
-        let header = work.select(&WORK_HEADER).next().ok_or(WorkError::ParsingError)?;
+        let header = work.select(&WORK_HEADER).next().context("header")?;
 
-        let title = header.select(&TITLE).next().ok_or(WorkError::ParsingError)?.inner_html();
-        let author = header.select(&AUTHOR).next().ok_or(WorkError::ParsingError)?.inner_html();
+        // Text fields are stored decoded, each output format escapes them itself.
+        let title = header.select(&TITLE).next().context("title")?.text().collect::<String>();
+        let author_element = header.select(&AUTHOR).next().context("author")?;
+        let author = author_element.text().collect::<String>();
+        let author_href = author_element.value().attr("href");
+        let author_link = author_href.map(WorkMetadata::normalize_author_url);
+        let (account_name, pseudo) = author_href.map(author_segments).unwrap_or_default();
+        let account_name = account_name.unwrap_or_else(|| author.clone());
+        let is_orphaned = author_link.as_ref().map_or(false, |href| href.contains("orphan_account"));
         let fandoms = header.select(&FANDOMS).flat_map(|e|
             Some(e.select(&TAG)
                 .next()?
-                .inner_html())
+                .text()
+                .collect())
         ).collect::<Vec<String>>();
-        let date = header.select(&DATE).next().ok_or(WorkError::ParsingError)?.inner_html();
+        let date = header.select(&DATE).next().context("date")?.text().collect::<String>();
 
-        let warnings = work.select(&WARNINGS).map(|e| e.inner_html()).collect::<Vec<String>>();
-        let relationships = work.select(&RELATIONSHIPS).map(|e| e.inner_html()).collect::<Vec<String>>();
-        let characters = work.select(&CHARACTERS).map(|e| e.inner_html()).collect::<Vec<String>>();
-        let tags = work.select(&TAGS).map(|e| e.inner_html()).collect::<Vec<String>>();
+        let rating = work.select(&RATING)
+            .next()
+            .map(|e| Rating::from_ao3(e.value().attr("title").unwrap_or_default()))
+            .unwrap_or(Rating::NotRated);
 
-        let stats = work.select(&STATS).next().ok_or(WorkError::ParsingError)?;
+        // Tag lists are optional, minimally tagged works simply have none.
+        let warnings = work.select(&WARNINGS).map(|e| e.text().collect()).collect::<Vec<String>>();
+        let relationships = work.select(&RELATIONSHIPS).map(|e| e.text().collect()).collect::<Vec<String>>();
+        let characters = work.select(&CHARACTERS).map(|e| e.text().collect()).collect::<Vec<String>>();
+        let tags = work.select(&TAGS).map(|e| e.text().collect()).collect::<Vec<String>>();
 
-        let language = stats.select(&LANGUAGE).next().map(|e| e.inner_html());
-        let words = stats.select(&WORDS).next().ok_or(WorkError::ParsingError)?.inner_html().replace(",", "").parse::<u64>().ok().ok_or(WorkError::ParsingError)?;
-        let chapters_string = stats.select(&CHAPTERS).next().ok_or(WorkError::ParsingError)?.inner_html();
+        let stats = work.select(&STATS).next().context("stats")?;
 
+        let language = stats.select(&LANGUAGE).next().map(|e| e.text().collect::<String>().trim().to_owned());
+        let language_code = language.as_deref().and_then(language_code).map(str::to_owned);
+        let words = match word_count(stats.select(&WORDS).next().context("words")?.inner_html().trim()) {
+            Ok(("", words)) => words,
+            _ => return Err(WorkError::ParseField { field: "words", source: "malformed count".into() }),
+        };
+        // The posted count links to the latest chapter on multi chapter works.
+        let chapters_string = stats.select(&CHAPTERS).next().context("chapters")?.text().collect::<String>();
 
-        let (chapter_value, total_chapters) = match chapters(&chapters_string) {
+        let (chapter_value, total_chapters) = match chapters(chapters_string.trim()) {
             Ok(("", (chapter, total))) => (chapter, total),
-            _ => return Err(WorkError::ParsingError),
+            _ => return Err(WorkError::ParseField { field: "chapters", source: "malformed chapter count".into() }),
         };
 
-        let kudos = stats.select(&KUDOS).next().ok_or(WorkError::ParsingError)?.inner_html().replace(",", "").parse::<u32>().ok().ok_or(WorkError::ParsingError)?;
-        let hits = stats.select(&HITS).next().ok_or(WorkError::ParsingError)?.inner_html().replace(",", "").parse::<u64>().ok().ok_or(WorkError::ParsingError)?;
+        let kudos = select_count_optional(stats, &KUDOS, "kudos")?;
+        let hits = select_count_optional(stats, &HITS, "hits")?;
+
+        let bookmarks = stats.select(&BOOKMARKS).next().and_then(|e| parse_count(&e.text().collect::<String>()).ok());
+        let comments = stats.select(&COMMENTS).next().and_then(|e| parse_count(&e.text().collect::<String>()).ok());
+        let revised_at = stats.select(&STATUS).next().map(|e| e.text().collect::<String>().trim().to_owned());
+
+        let summary = work.select(&SUMMARY).next().map(|e| e.inner_html().trim().to_owned());
+
+        let series = work.select(&SERIES)
+            .flat_map(|e| Some(SeriesRef {
+                id: e.value().attr("href")?.strip_prefix("/series/")?.parse().ok()?,
+                title: e.text().collect::<String>().trim().to_owned(),
+            }))
+            .collect();
+
+        let original_work_id = work.select(&ORIGINAL_WORK).next()
+            .and_then(|e| e.value().attr("href")?.strip_prefix("/works/")?.split('/').next()?.parse().ok());
+
+        let inspired_by = work.select(&INSPIRED_BY)
+            .flat_map(|e| {
+                let title = e.text().collect::<String>().trim().to_owned();
+
+                Some(WorkRef {
+                    id: e.value().attr("href")?.strip_prefix("/works/")?.split('/').next()?.parse().ok()?,
+                    title: (!title.is_empty()).then_some(title),
+                })
+            })
+            .collect();
 
         Ok(WorkMetadata {
             id,
             title,
             author,
+            author_link,
+            account_name,
+            pseudo,
+            is_orphaned,
             published_date: date,
+            revised_at,
+            rating,
             fandoms,
             warnings,
             relationships,
             characters,
             tags,
             language,
+            language_code,
             words,
             chapter: chapter_value,
             total_chapters,
             kudos,
             hits,
+            bookmarks,
+            comments,
+            summary,
+            chapters_index: Vec::new(),
+            chapters_list: None,
+            series,
+            original_work_id,
+            inspired_by,
+            relationship_categories: Vec::new(),
         })
     }
 }
 
 impl WorkMetadata {
     pub async fn work(id: u64) -> Result<Self, WorkError> {
-        let url = format!("https://archiveofourown.org/works/{}", id);
+        WorkMetadata::scrape_with_options(id, &reqwest::Client::new(), &ScrapingOptions::default()).await
+    }
+
+    /// Scrapes every ID from `start` to `end` inclusive, at most `concurrency`
+    /// at a time. Requests are started at least `BULK_SCRAPE_DELAY` apart
+    /// however high `concurrency` is, to keep the load on AO3 reasonable.
+    pub fn from_id_range_stream(start: u64, end: u64, concurrency: usize, client: Arc<reqwest::Client>) -> impl Stream<Item = (u64, Result<Self, WorkError>)> {
+        let mut interval = tokio::time::interval(BULK_SCRAPE_DELAY);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let interval = Arc::new(tokio::sync::Mutex::new(interval));
+
+        stream::iter(start..=end)
+            .map(move |id| {
+                let client = client.clone();
+                let interval = interval.clone();
+
+                async move {
+                    interval.lock().await.tick().await;
+
+                    (id, WorkMetadata::scrape_with_options(id, &client, &ScrapingOptions::default()).await)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    pub async fn scrape_with_proxy(id: u64, proxy_url: &str) -> Result<Self, WorkError> {
+        let client = proxied_client(proxy_url)?;
+
+        WorkMetadata::scrape_with_options(id, &client, &ScrapingOptions::default())
+            .await
+            .map_err(|err| match err {
+                WorkError::RequestError(err) if err.is_connect() => WorkError::ProxyError(err.to_string()),
+                err => err,
+            })
+    }
+
+    pub async fn scrape_with_options(id: u64, client: &reqwest::Client, options: &ScrapingOptions) -> Result<Self, WorkError> {
+        WorkMetadata::scrape(id, client, options, None).await
+    }
+
+    /// Scrapes the work logged in with the cookies in `jar`, storing any
+    /// rotated session cookies AO3 sends back.
+    pub async fn scrape_with_cookies(id: u64, client: &reqwest::Client, jar: &CookieJar) -> Result<Self, WorkError> {
+        WorkMetadata::scrape(id, client, &ScrapingOptions::default(), Some(jar)).await
+    }
+
+    #[tracing::instrument(skip(client, options, jar))]
+    async fn scrape(id: u64, client: &reqwest::Client, options: &ScrapingOptions, jar: Option<&CookieJar>) -> Result<Self, WorkError> {
+        let (html, chapters_list) = if env_flag("SCRAPE_CHAPTER_INDEX") {
+            let (html, chapters_list) = tokio::join!(
+                fetch_work_page(id, client, options, jar),
+                WorkMetadata::scrape_chapter_index(id, client),
+            );
+
+            let chapters_list = chapters_list
+                .map_err(|err| tracing::warn!(work_id = id, error = %err, "Could not scrape chapter index"))
+                .ok();
+
+            (html?, chapters_list)
+        } else {
+            (fetch_work_page(id, client, options, jar).await?, None)
+        };
+
+        WorkMetadata::from_work_page(id, &html, chapters_list, client, options).await
+    }
+
+    /// Scrapes the work only if it changed since the page with `last_etag`
+    /// was fetched, returning `None` on `304 Not Modified`. The ETag of the
+    /// new page is returned alongside the work.
+    ///
+    /// When AO3 sends no `ETag`, or doesn't honour `If-None-Match`, this is
+    /// just a full scrape. See also [`WorkMetadata::fetch_revised_at`].
+    pub async fn scrape_if_modified(id: u64, client: &reqwest::Client, jar: Option<&CookieJar>, last_etag: Option<&str>) -> Result<Option<(Self, Option<String>)>, WorkError> {
+        let mut headers = HeaderMap::new();
+
+        if let Some(etag) = last_etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+
+        let response = fetch_work_response(id, client, &ScrapingOptions::default(), jar, headers).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let etag = response.headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        let html = response.text().await?;
+
+        let chapters_list = if env_flag("SCRAPE_CHAPTER_INDEX") {
+            WorkMetadata::scrape_chapter_index(id, client)
+                .await
+                .map_err(|err| tracing::warn!(work_id = id, error = %err, "Could not scrape chapter index"))
+                .ok()
+        } else {
+            None
+        };
+
+        Ok(Some((WorkMetadata::from_work_page(id, &html, chapters_list, client, &ScrapingOptions::default()).await?, etag)))
+    }
+
+    async fn from_work_page(id: u64, html: &str, chapters_list: Option<Vec<ChapterRef>>, client: &reqwest::Client, options: &ScrapingOptions) -> Result<Self, WorkError> {
+        let mut work: WorkMetadata = {
+            let html = Html::parse_document(html);
+
+            match WorkMetadata::detect_html_structure_version(&html) {
+                AO3StructureVersion::V1 => {},
+                AO3StructureVersion::LoginRequired => return Err(WorkError::RestrictedWork),
+                AO3StructureVersion::Unknown => {
+                    let fingerprint = html.select(&WORK_META)
+                        .next()
+                        .map(|e| e.html())
+                        .unwrap_or_default();
+
+                    tracing::warn!("Unknown AO3 page structure for {}, fingerprint {:x}", id, Sha256::digest(fingerprint.as_bytes()));
+
+                    return Err(WorkError::UnknownStructure);
+                },
+            }
+
+            let mut work: WorkMetadata = (id, html.select(&WORK).next().ok_or(WorkError::WorkError)?).try_into()?;
+
+            if env_flag("SCRAPE_CHAPTER_INDEX") {
+                work.chapters_index = match &chapters_list {
+                    Some(chapters) => chapters.iter().map(|chapter| (chapter.number, chapter.id)).collect(),
+                    None => parse_chapter_select(&html),
+                };
+                work.chapters_list = chapters_list;
+            }
+
+            work
+        };
+
+        work.normalize_tags();
+        work.validate().map_err(WorkError::ValidationError)?;
+
+        if env_flag("VERIFY_WORD_COUNT") && work.is_complete() {
+            work.verify_word_count(client, options).await;
+        }
+
+        Ok(work)
+    }
 
-        let html = reqwest::get(url)
+    /// Fetches the work page but only parses its title and author.
+    pub async fn scrape_minimal(id: u64, client: &reqwest::Client, jar: Option<&CookieJar>) -> Result<WorkMetadataMinimal, WorkError> {
+        let html = fetch_work_page(id, client, &ScrapingOptions::default(), jar).await?;
+        let html = Html::parse_document(&html);
+
+        let header = html.select(&WORK)
+            .next()
+            .ok_or(WorkError::WorkError)?
+            .select(&WORK_HEADER)
+            .next()
+            .context("header")?;
+
+        Ok(WorkMetadataMinimal {
+            id,
+            title: header.select(&TITLE).next().context("title")?.text().collect(),
+            author: header.select(&AUTHOR).next().context("author")?.text().collect(),
+        })
+    }
+
+    /// Builds metadata from a single `li.work.blurb` as found on bookmark
+    /// pages, without fetching the work itself. Chapter indexes are never
+    /// present in blurbs.
+    pub fn from_bookmark_blurb(blurb: ElementRef<'_>) -> Result<Self, WorkError> {
+        WorkMetadata::from_blurb(blurb)
+    }
+
+    /// Builds metadata from a work blurb on a search or tag listing page.
+    /// Blurbs leave out the summary's formatting and the chapter index, and
+    /// stats such as bookmarks may be missing.
+    pub fn from_search_blurb(blurb: ElementRef<'_>) -> Result<Self, WorkError> {
+        WorkMetadata::from_blurb(blurb)
+    }
+
+    fn from_blurb(blurb: ElementRef<'_>) -> Result<Self, WorkError> {
+        let id = blurb.select(&TITLE)
+            .next()
+            .and_then(|e| e.value().attr("href")?.strip_prefix("/works/")?.parse::<u64>().ok())
+            .context("work id")?;
+
+        let mut work: WorkMetadata = (id, blurb).try_into()?;
+
+        work.normalize_tags();
+        work.validate().map_err(WorkError::ValidationError)?;
+
+        Ok(work)
+    }
+
+    /// Scrapes the work's `/navigate` page for the ID and title of every chapter.
+    pub async fn scrape_chapter_index(id: u64, client: &reqwest::Client) -> Result<Vec<ChapterRef>, WorkError> {
+        let html = client.get(format!("{}/navigate", ao3_work_url(id)))
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        let html = Html::parse_document(&html);
+
+        let chapters = html.select(&NAVIGATE_CHAPTERS)
+            .enumerate()
+            .flat_map(|(i, item)| {
+                let e = item.select(&NAVIGATE_LINK).next()?;
+                let chapter_id = e.value().attr("href")?.rsplit('/').next()?.parse::<u64>().ok()?;
+                let number = i as u16 + 1;
+
+                let published = item.select(&NAVIGATE_DATE).next()
+                    .map(|date| date.text().collect::<String>().trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')').to_owned());
+
+                let text = e.text().collect::<String>();
+                let title = text.trim()
+                    .strip_prefix(&format!("{}.", number))
+                    .unwrap_or(text.trim())
+                    .trim();
+
+                Some(ChapterRef {
+                    number,
+                    id: chapter_id,
+                    title: (!title.is_empty()).then(|| title.to_owned()),
+                    published,
+                })
+            })
+            .collect();
+
+        Ok(chapters)
+    }
+
+    /// Asks AO3 for the work's `Last-Modified` date with a `HEAD` request, as
+    /// a cheap way to tell whether it changed since `revised_at`. `None` when
+    /// AO3 doesn't send the header.
+    pub async fn fetch_revised_at(id: u64, client: &reqwest::Client, jar: Option<&CookieJar>) -> Result<Option<String>, WorkError> {
+        let mut request = client.head(ao3_work_url(id)).query(&ScrapingOptions::default().query());
+
+        if let Some(jar) = jar {
+            request = jar.apply(request);
+        }
+
+        let response = request.send().await?;
+
+        if let Some(jar) = jar {
+            jar.update(&response);
+        }
+
+        let revised_at = response.headers()
+            .get(reqwest::header::LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| PrimitiveDateTime::parse(value, format_description!("[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT")).ok())
+            .and_then(|modified| modified.date().format(format_description!("[year]-[month]-[day]")).ok());
+
+        Ok(revised_at)
+    }
+
+    /// Fetches up to five other works from the first series this work is part
+    /// of, parsed from the series page's blurbs.
+    pub async fn fetch_related_works(&self, client: &reqwest::Client) -> Result<Vec<WorkMetadata>, WorkError> {
+        let Some(series) = self.series.first() else {
+            return Ok(Vec::new());
+        };
+
+        let html = client.get(ao3_series_url(series.id))
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        let related = extract_search_blurbs(&html)
+            .into_iter()
+            .filter(|work| work.id != self.id)
+            .take(5)
+            .collect();
+
+        Ok(related)
+    }
+
+    /// Scrapes the usernames on the first page of the work's kudos. AO3 only
+    /// lists these to logged in users.
+    pub async fn scrape_kudos_users(id: u64, client: &reqwest::Client, jar: &CookieJar) -> Result<Vec<String>, WorkError> {
+        let response = jar.apply(client.get(format!("{}/kudos", ao3_work_url(id))))
+            .send()
+            .await?;
+
+        jar.update(&response);
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(WorkError::NotFound),
+            StatusCode::TOO_MANY_REQUESTS => return Err(WorkError::RateLimited),
+            _ => {},
+        }
+
+        let html = Html::parse_document(&response.text().await?);
+
+        let users = html.select(&KUDOS_USERS)
+            .map(|e| e.text().collect::<String>().trim().to_owned())
+            .filter(|user| !user.is_empty())
+            .take(100)
+            .collect();
+
+        Ok(users)
+    }
+
+    /// Fingerprints a fetched page against the selectors the scraper relies on.
+    pub fn detect_html_structure_version(html: &Html) -> AO3StructureVersion {
+        if html.select(&LOGIN).next().is_some() {
+            return AO3StructureVersion::LoginRequired;
+        }
+
+        let recognised = html.select(&WORK)
+            .next()
+            .map_or(false, |work| work.select(&WORK_HEADER).next().is_some() && work.select(&STATS).next().is_some());
+
+        if recognised {
+            AO3StructureVersion::V1
+        } else {
+            AO3StructureVersion::Unknown
+        }
+    }
+
+    /// Counts the words in the text of the most recent chapter, or `None`
+    /// when the chapter index wasn't scraped and its URL is unknown.
+    pub async fn last_chapter_scrape(&self, client: &reqwest::Client, options: &ScrapingOptions) -> Result<Option<u64>, WorkError> {
+        let Some(url) = self.chapter_url(self.chapter) else {
+            return Ok(None);
+        };
+
+        let html = client.get(url)
+            .query(&options.query())
+            .send()
             .await?
             .text()
             .await?;
 
         let html = Html::parse_document(&html);
 
-        Ok((id, html.select(&WORK).next().ok_or(WorkError::WorkError)?).try_into()?)
+        Ok(Some(html.select(&CHAPTER_TEXT)
+            .flat_map(|e| e.text())
+            .map(|text| text.split_whitespace().count() as u64)
+            .sum()))
+    }
+
+    /// Diagnostic only: logs when the counted words of the final chapter
+    /// disagree with the stats block by more than 10%. The stated count is
+    /// still what gets embedded. Only single chapter works can be compared
+    /// directly, as AO3 exposes no per-chapter counts.
+    async fn verify_word_count(&self, client: &reqwest::Client, options: &ScrapingOptions) {
+        let counted = match self.last_chapter_scrape(client, options).await {
+            Ok(Some(counted)) => counted,
+            Ok(None) => {
+                tracing::debug!(work_id = self.id, "Final chapter URL unknown, not verifying word count");
+                return;
+            },
+            Err(err) => {
+                tracing::warn!(work_id = self.id, error = %err, "Could not verify word count");
+                return;
+            },
+        };
+
+        if self.chapter != 1 {
+            tracing::info!("Counted {} words in the final chapter of {}", counted, self.id);
+            return;
+        }
+
+        // Podfics often have no stated word count to compare against.
+        if self.words > 0 && counted.abs_diff(self.words) * 10 > self.words {
+            tracing::warn!("Word count mismatch for {}: stated {}, counted {}", self.id, self.words, counted);
+        }
+    }
+
+    pub fn embed_url(&self) -> String {
+        let mut embed_url = format!(
+            "{}/oembed/v2/{}/{}/{}/{}/{}/{}",
+            host(),
+            self.id,
+            URL_SAFE_NO_PAD.encode(self.author_display().as_bytes()),
+            self.words,
+            self.chapter,
+            self.total_chapters
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| String::from("?")),
+            urlencoding::encode(&self.published_date)
+        );
+
+        let mut query = Vec::new();
+
+        // The profile link is built from the account, the pseud is only displayed.
+        if !self.is_orphaned && !self.account_name.is_empty() {
+            query.push(format!("account={}", urlencoding::encode(&self.account_name)));
+        }
+
+        if let Some(comments) = self.comments.filter(|&c| c > 0) {
+            query.push(format!("comments={}", comments));
+        }
+
+        if self.is_podfic() {
+            query.push(String::from("podfic=true"));
+        }
+
+        if !query.is_empty() {
+            embed_url.push('?');
+            embed_url.push_str(&query.join("&"));
+        }
+
+        embed_url
+    }
+
+    pub fn format_for_platform(&self, platform: Platform) -> WorkTemplate {
+        let relationships = if self.relationship_count() > 3 {
+            let top = join_quoted(self.top_relationships(3).into_iter().cloned().collect());
+            format!("{} and {} more", top, self.relationship_count() - 3)
+        } else {
+            join_quoted(self.relationships.clone())
+        };
+
+        let sections = [
+            ("📭", String::from("This work has been orphaned by its author."), self.is_orphaned),
+            ("🌐", format!("Translation of {}", self.original_work_url().unwrap_or_default()), self.is_translation() && self.original_work_id.is_some()),
+            ("🔁", format!("Remix of: {}", self.inspired_by.iter().map(|work| work.title.clone().unwrap_or_else(|| ao3_work_url(work.id))).join(", ")), self.is_remix()),
+            ("⚠️", join_quoted(self.warnings.clone()), true),
+            ("💞", relationships, true),
+            ("👤", join_quoted(self.characters.clone()), platform.includes_characters()),
+            ("🏷️", join_quoted(self.tags.clone()), true),
+        ];
+
+        let description = sections.into_iter()
+            .filter(|(_, _, included)| *included)
+            .map(|(emoji, joined, _)| {
+                if platform.uses_emoji() && !joined.is_empty() {
+                    format!("{} {}", emoji, joined)
+                } else {
+                    joined
+                }
+            })
+            .intersperse_with(|| String::from("\n"))
+            .collect::<String>();
+
+        let description = format!("{}\n{}", self.word_count_tier(), description);
+
+        let description = match platform.description_limit() {
+            Some(limit) => truncate_chars(&description, limit),
+            None => description,
+        };
+
+        WorkTemplate {
+            id: self.id,
+            url: self.ao3_url(),
+            title: match (self.is_crossover(), self.is_translation()) {
+                (true, true) => format!("⚡ CROSSOVER: Translation: {}", self.title),
+                (true, false) => format!("⚡ CROSSOVER: {}", self.title),
+                (false, true) => format!("Translation: {}", self.title),
+                (false, false) => self.title.clone(),
+            },
+            author: self.author_display().to_owned(),
+            description,
+            embed_url: self.embed_url(),
+            image_url: format!(
+                "{}/works/{}/thumbnail.svg",
+                host(),
+                self.id,
+            ),
+            json_ld: self.as_json_ld().to_string().replace("</", "<\\/"),
+            subjects: self.fandoms.iter().chain(self.tags.iter()).cloned().collect(),
+            fandom_links: self.fandoms.iter().map(|fandom| (fandom.clone(), WorkMetadata::ao3_fandom_url(fandom))).collect(),
+            tag_links: self.tags.iter().map(|tag| (tag.clone(), WorkMetadata::ao3_tag_url(tag))).collect(),
+            language: self.language.clone(),
+            locale: self.language_code.as_ref().map(|code| code.replace('-', "_")),
+            published_date: self.published_date.clone(),
+            updated_time: self.updated_date()
+                .filter(|updated| Some(*updated) != parse_date(&self.published_date))
+                .and_then(|updated| updated.format(format_description!("[year]-[month]-[day]")).ok()),
+        }
+    }
+
+    /// A guess at when an in-progress work will complete, as `YYYY-MM-DD`.
+    ///
+    /// This needs the chapter dates from the navigation page, so it is only
+    /// available with `SCRAPE_CHAPTER_INDEX=true`. The average gap between the
+    /// first and latest chapter is projected forward once for each remaining
+    /// chapter. `None` for complete works, works without a known chapter
+    /// total, and works with fewer than two dated chapters.
+    pub fn estimate_completion_date(&self) -> Option<String> {
+        let remaining = self.chapters_remaining().filter(|&remaining| remaining > 0)?;
+
+        let dates = self.chapters_list.as_ref()?
+            .iter()
+            .filter_map(|chapter| parse_date(chapter.published.as_deref()?))
+            .collect::<Vec<_>>();
+
+        let (first, latest) = (*dates.first()?, *dates.last()?);
+
+        if dates.len() < 2 || latest <= first {
+            return None;
+        }
+
+        let interval = (latest - first) / (dates.len() as i32 - 1);
+        let estimate = latest.checked_add(interval * remaining as i32)?;
+
+        estimate.format(format_description!("[year]-[month]-[day]")).ok()
+    }
+
+    /// Whether the work is an audio recording, going by the `Podfic`,
+    /// `Podfic & Podficced Works` and `Podfic Length` tags.
+    pub fn is_podfic(&self) -> bool {
+        self.tags.iter()
+            .chain(self.fandoms.iter())
+            .any(|tag| tag.trim().to_lowercase().starts_with("podfic"))
+    }
+
+    /// Whether the work is a fan translation, going by its `Translation` tags.
+    pub fn is_translation(&self) -> bool {
+        self.tags.iter().any(|tag| tag.trim().to_lowercase().starts_with("translation"))
+    }
+
+    pub fn original_work_id(&self) -> Option<u64> {
+        self.original_work_id
+    }
+
+    pub fn original_work_url(&self) -> Option<String> {
+        self.original_work_id.map(ao3_work_url)
+    }
+
+    pub fn is_remix(&self) -> bool {
+        !self.inspired_by.is_empty()
+    }
+
+    /// Whether the work is tagged with more than one fandom.
+    pub fn is_crossover(&self) -> bool {
+        self.fandoms.len() > 1
+    }
+
+    /// Every fandom of a crossover, or nothing for single fandom works.
+    pub fn crossover_fandoms(&self) -> &[String] {
+        if self.is_crossover() {
+            &self.fandoms
+        } else {
+            &[]
+        }
+    }
+
+    /// The date of the last update, parsed from `revised_at`.
+    pub fn updated_date(&self) -> Option<Date> {
+        parse_date(self.revised_at.as_deref()?)
+    }
+
+    pub fn ao3_url(&self) -> String {
+        ao3_work_url(self.id)
+    }
+
+    /// The author's profile, which orphaned works no longer have. This is
+    /// the main account's profile rather than the pseud's.
+    pub fn author_url(&self) -> Result<String, WorkError> {
+        if self.is_orphaned {
+            Err(WorkError::OrphanedWork)
+        } else if !self.account_name.is_empty() {
+            Ok(ao3_user_url(&self.account_name))
+        } else {
+            Ok(self.author_link.clone().unwrap_or_else(|| ao3_user_url(&self.author)))
+        }
+    }
+
+    pub fn ao3_tag_url(tag: &str) -> String {
+        ao3_tag_url(tag)
+    }
+
+    pub fn ao3_fandom_url(fandom: &str) -> String {
+        ao3_tag_url(fandom)
+    }
+
+    pub fn ao3_character_url(character: &str) -> String {
+        ao3_tag_url(character)
+    }
+
+    pub fn ao3_relationship_url(relationship: &str) -> String {
+        ao3_tag_url(relationship)
+    }
+
+    /// Makes an AO3 `href` absolute, leaving absolute URLs as they are.
+    pub fn normalize_author_url(href: &str) -> String {
+        if href.starts_with("//") {
+            format!("https:{}", href)
+        } else if href.starts_with('/') {
+            format!("{}{}", AO3_BASE_URL, href)
+        } else {
+            href.to_owned()
+        }
+    }
+
+    /// The author as shown in embeds.
+    pub fn author_display(&self) -> &str {
+        if self.is_orphaned {
+            "Orphaned Work"
+        } else {
+            &self.author
+        }
+    }
+
+    /// The summary with its HTML markup stripped.
+    pub fn summary_text(&self) -> Option<String> {
+        let summary = Html::parse_fragment(self.summary.as_deref()?);
+
+        let text = summary.root_element()
+            .text()
+            .flat_map(str::split_whitespace)
+            .join(" ");
+
+        Some(text)
+    }
+
+    /// Lowercases, trims and collapses whitespace in every tag list, then
+    /// sorts and deduplicates them so trivially different scrapes compare equal.
+    pub fn normalize_tags(&mut self) {
+        for tags in [&mut self.fandoms, &mut self.warnings, &mut self.relationships, &mut self.characters, &mut self.tags] {
+            for tag in tags.iter_mut() {
+                *tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+            }
+
+            tags.sort_unstable();
+            tags.dedup();
+        }
+    }
+
+    /// A hash of every scraped field except the ID, for telling whether a
+    /// re-scrape changed anything.
+    pub fn content_hash(&self) -> u64 {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("id");
+        }
+
+        xxh3_64(value.to_string().as_bytes())
+    }
+
+    /// Checks the scraped fields make sense together, reporting every problem found.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.id == 0 {
+            errors.push(ValidationError::MissingId);
+        }
+
+        if self.title.trim().is_empty() {
+            errors.push(ValidationError::EmptyTitle);
+        }
+
+        if self.author.trim().is_empty() {
+            errors.push(ValidationError::EmptyAuthor);
+        }
+
+        // Podfics are recordings and are often posted with no words at all.
+        if self.words == 0 && !self.is_podfic() {
+            errors.push(ValidationError::NoWords);
+        }
+
+        if self.chapter == 0 {
+            errors.push(ValidationError::NoChapters);
+        }
+
+        if let Some(total) = self.total_chapters.filter(|&total| total < self.chapter) {
+            errors.push(ValidationError::ChapterPastTotal { chapter: self.chapter, total });
+        }
+
+        if parse_date(&self.published_date).is_none() {
+            errors.push(ValidationError::MalformedDate(self.published_date.clone()));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn word_count_tier(&self) -> WordCountTier {
+        WordCountTier::from_words(self.words)
+    }
+
+    /// Minutes to read the posted chapters at 250 words a minute, rounded up.
+    pub fn reading_time_minutes(&self) -> u64 {
+        self.words.div_ceil(250)
+    }
+
+    /// Chapters still to be posted, when the total is known.
+    pub fn chapters_remaining(&self) -> Option<u16> {
+        self.total_chapters.map(|total| total.saturating_sub(self.chapter))
+    }
+
+    pub fn rating_is_explicit(&self) -> bool {
+        self.rating == Rating::Explicit
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total_chapters == Some(self.chapter)
+    }
+
+    /// The four digit year of the published date.
+    pub fn published_year(&self) -> Option<&str> {
+        self.published_date
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|part| part.len() == 4)
+    }
+
+    /// Days since the work was last updated, or since it was published when
+    /// it never has been.
+    pub fn days_since_update(&self) -> Option<i64> {
+        let updated = self.updated_date().or_else(|| parse_date(&self.published_date))?;
+
+        Some((OffsetDateTime::now_utc().date() - updated).whole_days())
+    }
+
+    /// How long a cached copy should live before it is scraped again, shorter
+    /// for works that are actively being updated.
+    pub fn recommended_ttl(&self) -> Duration {
+        const HOUR: u64 = 60 * 60;
+
+        if self.is_complete() {
+            return Duration::from_secs(24 * HOUR);
+        }
+
+        match self.days_since_update() {
+            Some(days) if days < 7 => Duration::from_secs(HOUR / 2),
+            Some(days) if days < 30 => Duration::from_secs(HOUR),
+            Some(days) if days < 365 => Duration::from_secs(6 * HOUR),
+            _ => Duration::from_secs(24 * HOUR),
+        }
+    }
+
+    /// The published date written out, e.g. `"January 15, 2023"`.
+    pub fn human_readable_date(&self) -> String {
+        parse_date(&self.published_date)
+            .and_then(|date| date.format(format_description!("[month repr:long] [day padding:none], [year]")).ok())
+            .unwrap_or_else(|| self.published_date.clone())
+    }
+
+    /// How long ago the work was published, e.g. `"3 days ago"`.
+    pub fn relative_date(&self) -> String {
+        relative_date_of(&self.published_date).unwrap_or_else(|| self.published_date.clone())
+    }
+
+    /// How likely the work is to have changed since it was scraped, from 0
+    /// to 100. Used to order background refreshes.
+    pub fn freshness_score(&self) -> u32 {
+        let mut score: u32 = 0;
+
+        if !self.is_complete() {
+            score += 50;
+        }
+
+        if self.days_since_update().map_or(false, |days| days < 7) {
+            score += 30;
+        }
+
+        if self.kudos > 10000 {
+            score += 10;
+        }
+
+        if self.is_orphaned {
+            score = score.saturating_sub(20);
+        }
+
+        score.min(100)
+    }
+
+    /// `"3/10"`, or `"3/?"` when the total is unknown.
+    pub fn chapters_display(&self) -> String {
+        format!(
+            "{}/{}",
+            self.chapter,
+            self.total_chapters
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| String::from("?")),
+        )
+    }
+
+    /// The work serialized as JSON, the same form it takes in cache exports.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+
+    /// A JSON:API document with the work as a single `works` resource.
+    pub fn to_jsonapi_document(&self) -> serde_json::Value {
+        let mut attributes = serde_json::to_value(self).unwrap_or_default();
+
+        if let Some(fields) = attributes.as_object_mut() {
+            fields.remove("id");
+        }
+
+        json!({
+            "data": {
+                "type": "works",
+                "id": self.id.to_string(),
+                "attributes": attributes,
+                "links": {
+                    "self": self.ao3_url(),
+                },
+            },
+        })
+    }
+
+    /// A `schema.org/CreativeWork` description of this work.
+    pub fn as_json_ld(&self) -> serde_json::Value {
+        json!({
+            "@context": "https://schema.org",
+            "@type": "CreativeWork",
+            "name": self.title,
+            "url": self.ao3_url(),
+            "author": {
+                "@type": "Person",
+                "name": self.author_display(),
+                "url": self.author_url().ok(),
+            },
+            "datePublished": self.published_date,
+            "wordCount": self.words,
+            "inLanguage": self.language_code.as_ref().or(self.language.as_ref()),
+            "genre": self.fandoms,
+            "keywords": self.tags,
+            "abstract": self.summary,
+            "publisher": {
+                "@type": "Organization",
+                "name": "Archive of Our Own",
+                "url": AO3_BASE_URL,
+            },
+        })
+    }
+
+    pub fn relationship_count(&self) -> usize {
+        self.relationships.len()
+    }
+
+    pub fn top_relationships(&self, n: usize) -> Vec<&String> {
+        self.relationships.iter().take(n).collect()
+    }
+
+    pub fn chapter_url(&self, chapter: u16) -> Option<String> {
+        self.chapters_index.iter()
+            .find(|(number, _)| *number == chapter)
+            .map(|(_, chapter_id)| ao3_chapter_url(self.id, *chapter_id))
+    }
+
+    /// `(title, url)` for every chapter in the scraped chapter list, using the
+    /// chapter number when a chapter has no title.
+    pub fn canonical_chapter_urls(&self) -> Vec<(String, String)> {
+        self.chapters_list
+            .iter()
+            .flatten()
+            .map(|chapter| (
+                chapter.title.clone().unwrap_or_else(|| chapter.number.to_string()),
+                ao3_chapter_url(self.id, chapter.id),
+            ))
+            .collect()
+    }
+
+    /// The URL of the most recently posted chapter, when the chapter index was scraped.
+    pub fn latest_chapter_url(&self) -> Option<String> {
+        match &self.chapters_list {
+            Some(chapters) => chapters.last().map(|chapter| ao3_chapter_url(self.id, chapter.id)),
+            None => self.chapter_url(self.chapter),
+        }
+    }
+
+    /// Renders this work as an Atom 1.0 `<entry>` fragment. `None` when
+    /// neither the update nor the published date parses, as Atom requires a
+    /// valid RFC 3339 `<updated>`.
+    pub fn to_atom_entry(&self) -> Option<String> {
+        let url = self.ao3_url();
+
+        let updated = self.updated_date()
+            .or_else(|| parse_date(&self.published_date))?
+            .with_time(Time::MIDNIGHT)
+            .assume_utc()
+            .format(&Rfc3339)
+            .ok()?;
+
+        let categories: String = self.fandoms.iter()
+            .map(|fandom| format!("<category term=\"{}\"/>", escape_xml(fandom)))
+            .collect();
+
+        Some(format!(
+            "<entry><title>{}</title><author><name>{}</name></author><id>{}</id><link href=\"{}\"/><updated>{}</updated><summary type=\"html\">{}</summary>{}</entry>",
+            escape_xml(&self.title),
+            escape_xml(self.author_display()),
+            url,
+            url,
+            updated,
+            escape_xml(self.summary.as_deref().unwrap_or_default()),
+            categories,
+        ))
+    }
+}
+
+async fn fetch_work_page(id: u64, client: &reqwest::Client, options: &ScrapingOptions, jar: Option<&CookieJar>) -> Result<String, WorkError> {
+    Ok(fetch_work_response(id, client, options, jar, HeaderMap::new()).await?.text().await?)
+}
+
+#[tracing::instrument(skip(client, options, jar, headers))]
+async fn fetch_work_response(id: u64, client: &reqwest::Client, options: &ScrapingOptions, jar: Option<&CookieJar>, headers: HeaderMap) -> Result<reqwest::Response, WorkError> {
+    let mut request = client.get(ao3_work_url(id)).query(&options.query()).headers(headers);
+
+    if let Some(jar) = jar {
+        request = jar.apply(request);
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    let request = request.headers(crate::telemetry::trace_context_headers());
+
+    let response = request.send().await?;
+
+    if let Some(jar) = jar {
+        jar.update(&response);
+    }
+
+    match response.status() {
+        StatusCode::NOT_FOUND => return Err(WorkError::NotFound),
+        StatusCode::TOO_MANY_REQUESTS => return Err(WorkError::RateLimited),
+        _ => {},
+    }
+
+    if response.url().query().map_or(false, |query| query.contains("restricted=true")) {
+        return Err(WorkError::RestrictedWork);
+    }
+
+    Ok(response)
+}
+
+/// Builds a client routing all traffic through `proxy_url`, e.g.
+/// `socks5://127.0.0.1:9050`.
+pub fn proxied_client(proxy_url: &str) -> Result<reqwest::Client, WorkError> {
+    let proxy = reqwest::Proxy::all(proxy_url).map_err(|err| WorkError::ProxyError(err.to_string()))?;
+
+    reqwest::Client::builder()
+        .proxy(proxy)
+        .build()
+        .map_err(|err| WorkError::ProxyError(err.to_string()))
+}
+
+/// AO3 usernames are restricted to ASCII letters, digits and underscores, with
+/// hyphens permitted in legacy accounts.
+pub fn ao3_username_is_valid(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Extracts the work IDs linked from the blurbs of an AO3 listing page, such
+/// as search results or a fandom's works.
+pub fn extract_work_ids(html: &str) -> Vec<u64> {
+    let html = Html::parse_document(html);
+
+    html.select(&BLURB_WORK_LINK)
+        .flat_map(|e| e.value().attr("href")?.strip_prefix("/works/")?.parse::<u64>().ok())
+        .unique()
+        .collect()
+}
+
+/// The account and pseud names from an author `href` like
+/// `/users/MainAccount/pseuds/PseudName`.
+fn author_segments(href: &str) -> (Option<String>, Option<String>) {
+    let segment = |name: &str| {
+        let mut parts = href.split('/').skip_while(|part| *part != name).skip(1);
+        parts.next()
+            .filter(|value| !value.is_empty())
+            .map(|value| urlencoding::decode(value).map_or_else(|_| value.to_owned(), |value| value.into_owned()))
+    };
+
+    (segment("users"), segment("pseuds"))
+}
+
+fn parse_date(date: &str) -> Option<Date> {
+    Date::parse(date.trim(), format_description!("[year]-[month]-[day]")).ok()
+}
+
+/// Days between a `YYYY-MM-DD` date and today.
+pub fn days_since(date: &str) -> Option<i64> {
+    Some((OffsetDateTime::now_utc().date() - parse_date(date)?).whole_days())
+}
+
+/// A `YYYY-MM-DD` date relative to today, e.g. `"2 months ago"`.
+pub fn relative_date_of(date: &str) -> Option<String> {
+    let days = days_since(date)?.max(0);
+
+    let (count, unit) = match days {
+        0 => return Some(String::from("today")),
+        1..=29 => (days, "day"),
+        30..=364 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+
+    Some(format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" }))
+}
+
+/// Parses every work blurb on a search result page, skipping blurbs that fail to parse.
+pub fn extract_search_blurbs(html: &str) -> Vec<WorkMetadata> {
+    let html = Html::parse_document(html);
+
+    html.select(&BLURB)
+        .filter_map(|blurb| WorkMetadata::from_search_blurb(blurb)
+            .map_err(|err| tracing::warn!("Could not parse search blurb: {}", err))
+            .ok())
+        .collect()
+}
+
+fn parse_chapter_select(html: &Html) -> Vec<(u16, u64)> {
+    html.select(&CHAPTER_INDEX)
+        .flat_map(|e| e.value().attr("value")?.parse::<u64>().ok())
+        .enumerate()
+        .map(|(i, chapter_id)| (i as u16 + 1, chapter_id))
+        .collect()
+}
+
+/// Formats a count with comma separated thousands, as AO3 displays them.
+pub(crate) fn format_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
+        }
+
+        formatted.push(c);
+    }
+
+    formatted
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// The work in `tests/fixtures/work.html`, parsed and normalized the way
+/// it would be cached.
+#[cfg(test)]
+pub(crate) fn fixture_work() -> WorkMetadata {
+    extract_search_blurbs(include_str!("../../tests/fixtures/work.html")).remove(0)
+}
+
+// The selectors are private statics, so these live here rather than in an
+// integration test under `tests/`.
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const WORK_PAGE: &str = include_str!("../../tests/fixtures/work.html");
+    const LOGIN_PAGE: &str = include_str!("../../tests/fixtures/login.html");
+
+    fn texts(html: &Html, selector: &Selector) -> Vec<String> {
+        html.select(selector).map(|e| e.text().collect::<String>().trim().to_owned()).collect()
+    }
+
+    #[test]
+    fn test_selectors_parse() {
+        validate_selectors();
+    }
+
+    #[test]
+    fn tag_selector_matches_tag_links() {
+        let html = Html::parse_fragment(r#"<a class="tag">Fluff</a>"#);
+
+        assert_eq!(texts(&html, &TAG), ["Fluff"]);
+    }
+
+    #[test]
+    fn title_and_author_selectors_match_heading_links() {
+        let html = Html::parse_fragment(r#"<h4><a href="/works/1">Title</a> by <a rel="author" href="/users/Test">Test</a></h4>"#);
+
+        assert_eq!(texts(&html, &TITLE), ["Title"]);
+        assert_eq!(texts(&html, &AUTHOR), ["Test"]);
+        assert_eq!(html.select(&AUTHOR).next().unwrap().value().attr("href"), Some("/users/Test"));
+    }
+
+    #[test]
+    fn chapters_selector_ignores_other_classes() {
+        let html = Html::parse_fragment(r#"<dl><dd class="not-chapters">1/1</dd></dl>"#);
+
+        assert!(html.select(&CHAPTERS).next().is_none());
+    }
+
+    #[test]
+    fn stat_selectors_match_their_own_dd() {
+        let html = Html::parse_fragment(r#"<dl class="stats"><dd class="words">1,234</dd><dd class="chapters">1/1</dd><dd class="kudos"><a href="/works/1/kudos">56</a></dd><dd class="hits">789</dd></dl>"#);
+
+        assert_eq!(html.select(&STATS).count(), 1);
+        assert_eq!(texts(&html, &WORDS), ["1,234"]);
+        assert_eq!(texts(&html, &CHAPTERS), ["1/1"]);
+        assert_eq!(texts(&html, &KUDOS), ["56"]);
+        assert_eq!(texts(&html, &HITS), ["789"]);
+        assert!(html.select(&BOOKMARKS).next().is_none());
+    }
+
+    #[test]
+    fn tag_list_selectors_only_match_their_class() {
+        let html = Html::parse_fragment(r#"<ul class="tags"><li class="warnings"><strong><a class="tag">No Archive Warnings Apply</a></strong></li><li class="relationships"><a class="tag">A/B</a></li><li class="characters"><a class="tag">A</a></li><li class="freeforms"><a class="tag">Fluff</a></li></ul>"#);
+
+        assert_eq!(texts(&html, &WARNINGS), ["No Archive Warnings Apply"]);
+        assert_eq!(texts(&html, &RELATIONSHIPS), ["A/B"]);
+        assert_eq!(texts(&html, &CHARACTERS), ["A"]);
+        assert_eq!(texts(&html, &TAGS), ["Fluff"]);
+    }
+
+    #[test]
+    fn link_selectors_require_the_right_href() {
+        let html = Html::parse_fragment(r#"<ul class="series"><li><a href="/series/1">Series</a></li><li><a href="/collections/2">Collection</a></li></ul>"#);
+
+        assert_eq!(texts(&html, &SERIES), ["Series"]);
+    }
+
+    #[test]
+    fn detects_structure_of_fixtures() {
+        assert_eq!(WorkMetadata::detect_html_structure_version(&Html::parse_document(WORK_PAGE)), AO3StructureVersion::V1);
+        assert_eq!(WorkMetadata::detect_html_structure_version(&Html::parse_document(LOGIN_PAGE)), AO3StructureVersion::LoginRequired);
+        assert_eq!(WorkMetadata::detect_html_structure_version(&Html::parse_document("<html><body></body></html>")), AO3StructureVersion::Unknown);
+    }
+
+    #[tokio::test]
+    async fn scrapes_work_page_fixture() {
+        let work = WorkMetadata::from_work_page(123456, WORK_PAGE, None, &reqwest::Client::new(), &ScrapingOptions::default()).await.unwrap();
+
+        assert_eq!(work.title, "The Long Way Round");
+        assert_eq!(work.author, "PenName (MainAccount)");
+        assert_eq!(work.account_name, "MainAccount");
+        assert_eq!(work.pseudo.as_deref(), Some("PenName"));
+        assert_eq!(work.author_link.as_deref(), Some("https://archiveofourown.org/users/MainAccount/pseuds/PenName"));
+        assert!(!work.is_orphaned);
+        assert_eq!(work.published_date, "2023-01-15");
+        assert_eq!(work.rating, Rating::Teen);
+        assert_eq!(work.fandoms, ["original work"]);
+        assert_eq!(work.warnings, ["no archive warnings apply"]);
+        assert_eq!(work.relationships, ["alice & carol", "alice/bob"]);
+        assert_eq!(work.characters, ["alice", "bob"]);
+        assert_eq!(work.tags, ["fluff", "road trip"]);
+        assert_eq!(work.language.as_deref(), Some("English"));
+        assert_eq!(work.words, 12_345);
+        assert_eq!((work.chapter, work.total_chapters), (3, Some(5)));
+        assert_eq!(work.kudos, 1_024);
+        assert_eq!(work.hits, 23_456);
+        assert_eq!(work.bookmarks, Some(87));
+        assert_eq!(work.comments, Some(42));
+        assert_eq!(work.summary_text().as_deref(), Some("Two friends take the scenic route home."));
+        assert_eq!(work.series, [SeriesRef { id: 98765, title: String::from("Scenic Routes") }]);
+    }
+
+    #[tokio::test]
+    async fn login_page_is_restricted() {
+        assert!(matches!(WorkMetadata::from_work_page(123456, LOGIN_PAGE, None, &reqwest::Client::new(), &ScrapingOptions::default()).await, Err(WorkError::RestrictedWork)));
+    }
+
+    #[test]
+    fn accepts_ao3_usernames() {
+        for username in ["Test", "some_user", "legacy-name", "User123"] {
+            assert!(ao3_username_is_valid(username), "{:?} should be valid", username);
+        }
+    }
+
+    #[test]
+    fn rejects_usernames_with_spaces_slashes_or_unicode() {
+        for username in ["", "two words", " padded", "a/b", "../admin", "Ünïcödé", "日本語", "emoji🙂"] {
+            assert!(!ao3_username_is_valid(username), "{:?} should be invalid", username);
+        }
+    }
+
+    #[test]
+    fn normalizes_relative_author_urls() {
+        assert_eq!(WorkMetadata::normalize_author_url("/users/Test"), "https://archiveofourown.org/users/Test");
+    }
+
+    #[test]
+    fn keeps_absolute_author_urls() {
+        assert_eq!(WorkMetadata::normalize_author_url("https://archiveofourown.org/users/Test"), "https://archiveofourown.org/users/Test");
+    }
+
+    #[test]
+    fn normalizes_protocol_relative_author_urls() {
+        assert_eq!(WorkMetadata::normalize_author_url("//archiveofourown.org/users/Test"), "https://archiveofourown.org/users/Test");
+    }
+
+    #[test]
+    fn normalizes_pseud_author_urls() {
+        let href = "/users/MainAccount/pseuds/PenName";
+
+        assert_eq!(WorkMetadata::normalize_author_url(href), "https://archiveofourown.org/users/MainAccount/pseuds/PenName");
+        assert_eq!(author_segments(href), (Some(String::from("MainAccount")), Some(String::from("PenName"))));
+    }
+
+    #[test]
+    fn word_count_tier_boundaries() {
+        let cases = [
+            (0, WordCountTier::Drabble),
+            (999, WordCountTier::Drabble),
+            (1_000, WordCountTier::Oneshot),
+            (9_999, WordCountTier::Oneshot),
+            (10_000, WordCountTier::Shortfic),
+            (49_999, WordCountTier::Shortfic),
+            (50_000, WordCountTier::Longfic),
+            (99_999, WordCountTier::Longfic),
+            (100_000, WordCountTier::Epic),
+            (499_999, WordCountTier::Epic),
+            (500_000, WordCountTier::Behemoth),
+        ];
+
+        for (words, tier) in cases {
+            assert_eq!(WordCountTier::from_words(words), tier, "{} words", words);
+        }
+    }
+
+    #[test]
+    fn single_fandom_work_is_not_crossover() {
+        let work = fixture_work();
+
+        assert!(!work.is_crossover());
+        assert!(work.crossover_fandoms().is_empty());
+        assert_eq!(work.format_for_platform(Platform::Generic).title, "The Long Way Round");
+    }
+
+    #[test]
+    fn multi_fandom_work_is_crossover() {
+        let mut work = fixture_work();
+        work.fandoms = vec![String::from("Original Work"), String::from("Star Trek")];
+
+        assert!(work.is_crossover());
+        assert_eq!(work.crossover_fandoms(), ["Original Work", "Star Trek"]);
+        assert_eq!(work.format_for_platform(Platform::Generic).title, "⚡ CROSSOVER: The Long Way Round");
+    }
+
+    #[test]
+    fn works_without_words_are_invalid() {
+        let mut work = fixture_work();
+        work.words = 0;
+
+        assert_eq!(work.validate(), Err(vec![ValidationError::NoWords]));
+    }
+
+    #[test]
+    fn podfics_without_words_are_valid() {
+        let mut work = fixture_work();
+        work.words = 0;
+        work.tags.push(String::from("Podfic"));
+
+        assert_eq!(work.validate(), Ok(()));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let work = fixture_work();
+        let decoded = WorkMetadata::from_bytes(&work.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.content_hash(), work.content_hash());
+    }
+
+    #[test]
+    fn atom_entry_has_rfc3339_updated() {
+        let entry = fixture_work().to_atom_entry().unwrap();
+
+        assert!(entry.contains("<updated>2023-01-15T00:00:00Z</updated>"));
+    }
+
+    #[test]
+    fn atom_entry_prefers_revised_date() {
+        let mut work = fixture_work();
+        work.revised_at = Some(String::from("2023-02-01"));
+
+        assert!(work.to_atom_entry().unwrap().contains("<updated>2023-02-01T00:00:00Z</updated>"));
+    }
+
+    #[test]
+    fn days_since_update_prefers_revised_date() {
+        let mut work = fixture_work();
+        let stale_score = work.freshness_score();
+
+        assert!(work.days_since_update().unwrap() > 7);
+
+        work.revised_at = Some(OffsetDateTime::now_utc().date().format(format_description!("[year]-[month]-[day]")).unwrap());
+
+        assert_eq!(work.days_since_update(), Some(0));
+        assert_eq!(work.freshness_score(), stale_score + 30);
+    }
+
+    #[test]
+    fn atom_entry_skips_unparseable_dates() {
+        let mut work = fixture_work();
+        work.published_date = String::from("15 Jan 2023");
+
+        assert_eq!(work.to_atom_entry(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_thousands_groups() {
+        for count in ["12,34", "1234,567", "1,2345", ",123", "123,", "1,,234"] {
+            assert!(parse_count::<u64>(count).is_err(), "{:?} should not parse", count);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn parses_formatted_counts(n: u64) {
+            prop_assert_eq!(parse_count::<u64>(&format_thousands(n)).unwrap(), n);
+        }
+
+        #[test]
+        fn parses_plain_counts(n: u64) {
+            prop_assert_eq!(parse_count::<u64>(&n.to_string()).unwrap(), n);
+        }
+
+        #[test]
+        fn rejects_leading_groups_over_three_digits(count in "[1-9][0-9]{3,8}(,[0-9]{3}){1,3}") {
+            prop_assert!(parse_count::<u64>(&count).is_err());
+        }
     }
 }
@@ -1 +1,19 @@
+pub mod access_log;
+pub mod cache;
+pub mod changes;
+pub mod discord;
+pub mod export;
+pub mod fandoms;
+pub mod feed;
+pub mod i18n;
+pub mod languages;
 pub mod meta;
+pub mod platform;
+pub mod preview;
+pub mod privacy;
+pub mod relationships;
+pub mod session;
+pub mod stats;
+pub mod thumbnail;
+pub mod urls;
+pub mod warnings;
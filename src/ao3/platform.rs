@@ -0,0 +1,44 @@
+/// The service a bot request originates from, which decides how much of a
+/// work's metadata fits in its embed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Discord,
+    Twitter,
+    Mastodon,
+    Generic,
+    SlackUnfurl,
+}
+
+impl Platform {
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        if user_agent.contains("Discordbot") {
+            Platform::Discord
+        } else if user_agent.contains("Twitterbot") {
+            Platform::Twitter
+        } else if user_agent.contains("Mastodon") {
+            Platform::Mastodon
+        } else if user_agent.contains("Slackbot") {
+            Platform::SlackUnfurl
+        } else {
+            Platform::Generic
+        }
+    }
+
+    pub fn description_limit(self) -> Option<usize> {
+        match self {
+            Platform::Discord => Some(2048),
+            Platform::Twitter => Some(280),
+            Platform::Mastodon => Some(500),
+            Platform::SlackUnfurl => Some(300),
+            Platform::Generic => None,
+        }
+    }
+
+    pub fn uses_emoji(self) -> bool {
+        matches!(self, Platform::Discord | Platform::Mastodon | Platform::SlackUnfurl)
+    }
+
+    pub fn includes_characters(self) -> bool {
+        !matches!(self, Platform::Twitter)
+    }
+}
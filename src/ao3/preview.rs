@@ -0,0 +1,42 @@
+use askama::Template;
+
+use super::meta::{Rating, WorkMetadata, format_thousands, truncate_chars};
+
+const SUMMARY_LIMIT: usize = 300;
+
+/// A self-contained HTML hover card for a work, with no external scripts or styles.
+#[derive(Debug, Template)]
+#[template(path = "preview.html")]
+pub struct PreviewTemplate {
+    pub url: String,
+    pub title: String,
+    pub author: String,
+    pub fandoms: Vec<String>,
+    pub rating: String,
+    pub rating_class: &'static str,
+    pub words: String,
+    pub status: &'static str,
+    pub summary: Option<String>,
+}
+
+impl From<&WorkMetadata> for PreviewTemplate {
+    fn from(work: &WorkMetadata) -> Self {
+        PreviewTemplate {
+            url: work.ao3_url(),
+            title: work.title.clone(),
+            author: work.author_display().to_owned(),
+            fandoms: work.fandoms.clone(),
+            rating: work.rating.to_string(),
+            rating_class: match work.rating {
+                Rating::General => "general",
+                Rating::Teen => "teen",
+                Rating::Mature => "mature",
+                Rating::Explicit => "explicit",
+                Rating::NotRated => "not-rated",
+            },
+            words: format_thousands(work.words),
+            status: if work.is_complete() { "Complete" } else { "In progress" },
+            summary: work.summary_text().map(|summary| truncate_chars(&summary, SUMMARY_LIMIT)),
+        }
+    }
+}
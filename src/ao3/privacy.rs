@@ -0,0 +1,32 @@
+use serde::Serialize;
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::meta::WorkMetadata;
+
+/// Hashes of a work's identifying fields, for analytics that can be
+/// aggregated without storing author names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrivacyHash {
+    pub author_hash: u64,
+    pub title_hash: u64,
+    pub fandom_hashes: Vec<u64>,
+}
+
+fn hash(s: &str) -> u64 {
+    xxh3_64(s.trim().to_lowercase().as_bytes())
+}
+
+impl WorkMetadata {
+    pub fn privacy_hash(&self) -> PrivacyHash {
+        PrivacyHash {
+            author_hash: hash(&self.author),
+            title_hash: hash(&self.title),
+            fandom_hashes: self.fandoms.iter().map(|fandom| hash(fandom)).collect(),
+        }
+    }
+}
+
+/// The hash an author name would have in a [`PrivacyHash`].
+pub fn author_hash(author: &str) -> u64 {
+    hash(author)
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use futures::{StreamExt, stream};
+use lazy_static::lazy_static;
+use moka::future::Cache;
+use schemars::JsonSchema;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use super::{meta::{WorkError, WorkMetadata}, urls::ao3_tag_url};
+
+/// Most category lookups in flight at once for a single work.
+const CATEGORY_LOOKUP_CONCURRENCY: usize = 4;
+
+lazy_static! {
+    static ref CATEGORY_FILTERS: Selector = Selector::parse("#include_category_tags li label").unwrap();
+
+    /// Categories change rarely, and each lookup is a full AO3 page load.
+    static ref CATEGORY_CACHE: Cache<String, Option<RelationshipCategory>> = Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(Duration::from_secs(7 * 24 * 60 * 60))
+        .build();
+}
+
+/// One of AO3's work categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum RelationshipCategory {
+    #[serde(rename = "F/F")]
+    FF,
+    #[serde(rename = "F/M")]
+    FM,
+    Gen,
+    #[serde(rename = "M/M")]
+    MM,
+    Multi,
+    Other,
+}
+
+impl RelationshipCategory {
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "F/F" => Some(RelationshipCategory::FF),
+            "F/M" => Some(RelationshipCategory::FM),
+            "Gen" => Some(RelationshipCategory::Gen),
+            "M/M" => Some(RelationshipCategory::MM),
+            "Multi" => Some(RelationshipCategory::Multi),
+            "Other" => Some(RelationshipCategory::Other),
+            _ => None,
+        }
+    }
+}
+
+/// A relationship tag split into the characters it pairs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "characters", rename_all = "snake_case")]
+pub enum RelationshipKind {
+    /// `"A/B"`
+    Romantic(Vec<String>),
+    /// `"A & B"`
+    Platonic(Vec<String>),
+}
+
+impl RelationshipKind {
+    pub fn characters(&self) -> &[String] {
+        match self {
+            RelationshipKind::Romantic(characters) | RelationshipKind::Platonic(characters) => characters,
+        }
+    }
+}
+
+/// Splits an AO3 relationship tag on `/` for romantic or `&` for platonic pairings.
+pub fn parse_relationship(relationship: &str) -> RelationshipKind {
+    let split = |separator: char| relationship
+        .split(separator)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect();
+
+    if relationship.contains('/') {
+        RelationshipKind::Romantic(split('/'))
+    } else {
+        RelationshipKind::Platonic(split('&'))
+    }
+}
+
+/// The most common category among the works tagged with `relationship`,
+/// read from the category filter counts on the tag's works listing. AO3
+/// doesn't categorise relationship tags themselves.
+async fn relationship_category(relationship: &str, client: &reqwest::Client) -> Result<Option<RelationshipCategory>, WorkError> {
+    if let Some(category) = CATEGORY_CACHE.get(relationship) {
+        return Ok(category);
+    }
+
+    let html = client.get(ao3_tag_url(relationship))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let category = Html::parse_document(&html)
+        .select(&CATEGORY_FILTERS)
+        .filter_map(|label| {
+            let text = label.text().collect::<String>();
+            let (name, count) = text.trim().split_once(" (")?;
+            let count = count.trim_end_matches(')').replace(',', "").parse::<u64>().ok()?;
+
+            Some((RelationshipCategory::from_label(name.trim())?, count))
+        })
+        .max_by_key(|(_, count)| *count)
+        .map(|(category, _)| category);
+
+    CATEGORY_CACHE.insert(relationship.to_owned(), category).await;
+
+    Ok(category)
+}
+
+impl WorkMetadata {
+    /// Looks up the category of each relationship, one AO3 request per
+    /// relationship not looked up in the last week, a few at a time.
+    /// Relationships whose lookup fails are left out.
+    pub async fn enrich_relationships(&mut self, client: &reqwest::Client) {
+        let id = self.id;
+
+        let categories: Vec<(String, RelationshipCategory)> = stream::iter(&self.relationships)
+            .map(move |relationship| async move {
+                match relationship_category(relationship, client).await {
+                    Ok(category) => Some((relationship.clone(), category?)),
+                    Err(err) => {
+                        tracing::warn!(work_id = id, relationship = %relationship, error = %err, "Could not look up relationship category");
+                        None
+                    },
+                }
+            })
+            .buffered(CATEGORY_LOOKUP_CONCURRENCY)
+            .filter_map(|category| async move { category })
+            .collect()
+            .await;
+
+        self.relationship_categories = categories;
+    }
+
+    /// Every character named in the work's relationships, without duplicates.
+    pub fn all_relationship_characters(&self) -> Vec<String> {
+        let mut characters = Vec::new();
+
+        for relationship in &self.relationships {
+            for character in parse_relationship(relationship).characters() {
+                if !characters.contains(character) {
+                    characters.push(character.clone());
+                }
+            }
+        }
+
+        characters
+    }
+}
@@ -0,0 +1,38 @@
+use reqwest::{RequestBuilder, Response, Url, cookie::{CookieStore, Jar}, header::COOKIE};
+
+use super::urls::AO3_BASE_URL;
+
+/// AO3 session cookies, kept up to date as AO3 rotates the session token.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    jar: Jar,
+}
+
+impl CookieJar {
+    /// A jar logged in with the given `_otwarchive_session` value.
+    pub fn from_session(session: &str) -> Self {
+        let jar = Jar::default();
+        jar.add_cookie_str(&format!("_otwarchive_session={}; Path=/", session), &ao3_url());
+
+        CookieJar { jar }
+    }
+
+    /// Attaches the stored cookies for AO3 to `request`.
+    pub(crate) fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self.jar.cookies(&ao3_url()) {
+            Some(cookies) => request.header(COOKIE, cookies),
+            None => request,
+        }
+    }
+
+    /// Stores any cookies AO3 set on `response`.
+    pub(crate) fn update(&self, response: &Response) {
+        let mut set_cookies = response.headers().get_all(reqwest::header::SET_COOKIE).iter();
+
+        self.jar.set_cookies(&mut set_cookies, response.url());
+    }
+}
+
+fn ao3_url() -> Url {
+    Url::parse(AO3_BASE_URL).expect("AO3_BASE_URL is a valid URL")
+}
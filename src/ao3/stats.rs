@@ -0,0 +1,43 @@
+use super::meta::WorkMetadata;
+
+/// Statistics over every cached work, for putting a single work in context.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalStats {
+    /// The word count at each percentile from 0 to 100, ascending.
+    pub word_count_percentiles: Vec<u64>,
+}
+
+impl GlobalStats {
+    pub fn from_word_counts(mut word_counts: Vec<u64>) -> Self {
+        if word_counts.is_empty() {
+            return GlobalStats::default();
+        }
+
+        word_counts.sort_unstable();
+
+        let last = word_counts.len() - 1;
+
+        GlobalStats {
+            word_count_percentiles: (0..=100)
+                .map(|percentile| word_counts[last * percentile / 100])
+                .collect(),
+        }
+    }
+
+    /// The percentage of cached works with fewer words than `words`.
+    pub fn word_count_percentile(&self, words: u64) -> f64 {
+        if self.word_count_percentiles.is_empty() {
+            return 0.0;
+        }
+
+        let below = self.word_count_percentiles.partition_point(|&percentile| percentile < words);
+
+        below as f64 * 100.0 / self.word_count_percentiles.len() as f64
+    }
+}
+
+impl WorkMetadata {
+    pub fn word_count_percentile(&self, stats: &GlobalStats) -> f64 {
+        stats.word_count_percentile(self.words)
+    }
+}
@@ -0,0 +1,45 @@
+use askama::Template;
+
+use super::meta::{WorkMetadata, wrap_words};
+
+const TITLE_LINE_WIDTH: usize = 30;
+const TITLE_MAX_LINES: usize = 3;
+
+/// A 600×315 OpenGraph image card for a work.
+#[derive(Debug, Template)]
+#[template(path = "thumbnail.svg", escape = "html")]
+pub struct SvgTemplate {
+    pub title: String,
+    pub title_lines: Vec<String>,
+    pub author: String,
+    pub fandom: String,
+    pub rating: String,
+}
+
+impl From<&WorkMetadata> for SvgTemplate {
+    fn from(work: &WorkMetadata) -> Self {
+        SvgTemplate {
+            title: work.title.clone(),
+            title_lines: wrap(&work.title, TITLE_LINE_WIDTH, TITLE_MAX_LINES),
+            author: work.author_display().to_owned(),
+            fandom: work.fandoms.first().cloned().unwrap_or_default(),
+            rating: work.rating.to_string(),
+        }
+    }
+}
+
+/// Wraps `text` like [`wrap_words`] into at most `max_lines` lines,
+/// ellipsising the last line if the text does not fit.
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines = wrap_words(text, width);
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+
+        if let Some(last) = lines.last_mut() {
+            last.push('…');
+        }
+    }
+
+    lines
+}
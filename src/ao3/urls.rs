@@ -0,0 +1,37 @@
+use std::env;
+
+pub const AO3_BASE_URL: &str = "https://archiveofourown.org";
+
+pub fn ao3_work_url(id: u64) -> String {
+    format!("{}/works/{}", AO3_BASE_URL, id)
+}
+
+pub fn ao3_chapter_url(work_id: u64, chapter_id: u64) -> String {
+    format!("{}/works/{}/chapters/{}", AO3_BASE_URL, work_id, chapter_id)
+}
+
+pub fn ao3_user_url(username: &str) -> String {
+    format!("{}/users/{}", AO3_BASE_URL, urlencoding::encode(username))
+}
+
+pub fn ao3_series_url(id: u64) -> String {
+    format!("{}/series/{}", AO3_BASE_URL, id)
+}
+
+/// The works listing for a tag. AO3 swaps characters that would break its
+/// routes for `*x*` escapes before the name is percent encoded.
+pub fn ao3_tag_url(tag: &str) -> String {
+    let escaped = tag
+        .replace('/', "*s*")
+        .replace('&', "*a*")
+        .replace('.', "*d*")
+        .replace('?', "*q*")
+        .replace('#', "*h*");
+
+    format!("{}/tags/{}/works", AO3_BASE_URL, urlencoding::encode(&escaped))
+}
+
+/// The public base URL of this service, from the `HOST` env var.
+pub fn host() -> String {
+    env::var("HOST").unwrap_or_else(|_| String::from("http://localhost:3000"))
+}
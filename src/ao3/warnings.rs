@@ -0,0 +1,142 @@
+use phf::phf_set;
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+
+use super::meta::WorkMetadata;
+
+/// A rough severity for a work's content warnings, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CwSeverity {
+    Safe,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+/// One of AO3's archive warnings, or `Unknown` for anything outside its fixed vocabulary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentWarning {
+    GraphicViolence,
+    MajorCharacterDeath,
+    NoArchiveWarningsApply,
+    ChooseNotToWarn,
+    RapeNonCon,
+    Underage,
+    Unknown(String),
+}
+
+impl ContentWarning {
+    pub fn from_warning(warning: &str) -> Self {
+        match warning.trim().to_lowercase().as_str() {
+            "graphic depictions of violence" => ContentWarning::GraphicViolence,
+            "major character death" => ContentWarning::MajorCharacterDeath,
+            "no archive warnings apply" => ContentWarning::NoArchiveWarningsApply,
+            "choose not to warn" | "choose not to use archive warnings" | "creator chose not to use archive warnings" => ContentWarning::ChooseNotToWarn,
+            "rape/non-con" => ContentWarning::RapeNonCon,
+            "underage" | "underage sex" => ContentWarning::Underage,
+            _ => ContentWarning::Unknown(warning.trim().to_owned()),
+        }
+    }
+
+    /// The warning as AO3 words it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ContentWarning::GraphicViolence => "Graphic Depictions of Violence",
+            ContentWarning::MajorCharacterDeath => "Major Character Death",
+            ContentWarning::NoArchiveWarningsApply => "No Archive Warnings Apply",
+            ContentWarning::ChooseNotToWarn => "Choose Not To Warn",
+            ContentWarning::RapeNonCon => "Rape/Non-Con",
+            ContentWarning::Underage => "Underage",
+            ContentWarning::Unknown(warning) => warning,
+        }
+    }
+
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            ContentWarning::GraphicViolence => "GraphicViolence",
+            ContentWarning::MajorCharacterDeath => "MajorCharacterDeath",
+            ContentWarning::NoArchiveWarningsApply => "NoArchiveWarningsApply",
+            ContentWarning::ChooseNotToWarn => "ChooseNotToWarn",
+            ContentWarning::RapeNonCon => "RapeNonCon",
+            ContentWarning::Underage => "Underage",
+            ContentWarning::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+/// Serializes as `{ "warning": ..., "variant": ... }` so consumers get both
+/// the text and something stable to match on.
+impl Serialize for ContentWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut warning = serializer.serialize_struct("ContentWarning", 2)?;
+        warning.serialize_field("warning", self.as_str())?;
+        warning.serialize_field("variant", self.variant_name())?;
+        warning.end()
+    }
+}
+
+/// AO3's major archive warnings.
+static SEVERE: phf::Set<&'static str> = phf_set! {
+    "graphic depictions of violence",
+    "major character death",
+    "rape/non-con",
+    "underage",
+    "underage sex",
+};
+
+static MODERATE: phf::Set<&'static str> = phf_set! {
+    "creator chose not to use archive warnings",
+    "choose not to use archive warnings",
+    "choose not to warn",
+    "self-harm",
+    "suicide",
+    "suicidal thoughts",
+    "attempted suicide",
+    "torture",
+    "child abuse",
+    "domestic violence",
+    "emotional abuse",
+    "drug abuse",
+    "eating disorders",
+};
+
+static MILD: phf::Set<&'static str> = phf_set! {
+    "angst",
+    "hurt/comfort",
+    "blood",
+    "minor injuries",
+    "violence",
+    "character death",
+    "canon-typical violence",
+    "alcohol",
+    "grief/mourning",
+};
+
+impl WorkMetadata {
+    pub fn content_warnings(&self) -> Vec<ContentWarning> {
+        self.warnings.iter()
+            .map(|warning| ContentWarning::from_warning(warning))
+            .collect()
+    }
+
+    /// The most severe content warning among the work's warnings and tags.
+    pub fn cw_severity(&self) -> CwSeverity {
+        self.warnings.iter()
+            .chain(self.tags.iter())
+            .map(|tag| {
+                let tag = tag.trim().to_lowercase();
+
+                if SEVERE.contains(tag.as_str()) {
+                    CwSeverity::Severe
+                } else if MODERATE.contains(tag.as_str()) {
+                    CwSeverity::Moderate
+                } else if MILD.contains(tag.as_str()) {
+                    CwSeverity::Mild
+                } else {
+                    CwSeverity::Safe
+                }
+            })
+            .max()
+            .unwrap_or(CwSeverity::Safe)
+    }
+}
@@ -0,0 +1,87 @@
+use std::process::ExitCode;
+
+use ao3_embed::ao3::meta::WorkMetadata;
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Table,
+}
+
+/// Scrapes a single AO3 work and prints its metadata.
+#[derive(Debug, Parser)]
+struct Args {
+    /// The AO3 work ID.
+    id: u64,
+    #[arg(long, value_enum, default_value = "table")]
+    format: Format,
+}
+
+fn list(values: &[String]) -> String {
+    values.join(", ")
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref()
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| String::from("-"))
+}
+
+fn print_table(work: &WorkMetadata) {
+    let rows = [
+        ("ID", work.id.to_string()),
+        ("Title", work.title.clone()),
+        ("Author", work.author.clone()),
+        ("Published", work.published_date.clone()),
+        ("Rating", work.rating.to_string()),
+        ("Fandoms", list(&work.fandoms)),
+        ("Warnings", list(&work.warnings)),
+        ("Relationships", list(&work.relationships)),
+        ("Characters", list(&work.characters)),
+        ("Tags", list(&work.tags)),
+        ("Language", optional(&work.language)),
+        ("Words", work.words.to_string()),
+        ("Chapters", work.chapters_display()),
+        ("Kudos", work.kudos.to_string()),
+        ("Hits", work.hits.to_string()),
+        ("Bookmarks", optional(&work.bookmarks)),
+        ("Comments", optional(&work.comments)),
+        ("Summary", work.summary_text().unwrap_or_else(|| String::from("-"))),
+    ];
+
+    let width = rows.iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    for (name, value) in rows {
+        println!("\x1b[1m{:<width$}\x1b[0m  {}", name, value, width = width);
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let work = match WorkMetadata::work(args.id).await {
+        Ok(work) => work,
+        Err(err) => {
+            eprintln!("error: {}", err.with_context(args.id));
+            return ExitCode::FAILURE;
+        },
+    };
+
+    match args.format {
+        Format::Json => match serde_json::to_string_pretty(&work) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return ExitCode::FAILURE;
+            },
+        },
+        Format::Table => print_table(&work),
+    }
+
+    ExitCode::SUCCESS
+}
@@ -1,2 +1,9 @@
+use std::env;
+
 pub mod ao3;
+#[cfg(feature = "opentelemetry")]
+pub mod telemetry;
 
+pub fn env_flag(name: &str) -> bool {
+    env::var(name).map(|value| value == "true").unwrap_or(false)
+}
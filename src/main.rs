@@ -1,24 +1,288 @@
-use std::{sync::Arc, env};
+use std::{cmp::Reverse, fs::File, io::{BufWriter, Write}, path::PathBuf, sync::{Arc, Mutex, RwLock, RwLockReadGuard, atomic::{AtomicU64, Ordering}}, env, time::{Duration, Instant, SystemTime, UNIX_EPOCH}, collections::HashSet};
 
-use ao3_embed::ao3::meta::{WorkMetadata, WorkTemplate};
-use axum::{Router, extract::{State, Path, OriginalUri}, response::{IntoResponse, Response, Redirect, Html}, routing::get, Json, TypedHeader, headers::UserAgent, http::Uri};
+use ao3_embed::{env_flag, ao3::{access_log::{AccessLog, AccessRecord}, changes::{MetadataChange, WorkChangelog}, discord::DiscordEmbed, fandoms::FandomIndex, meta::{WorkMetadata, WorkMetadataMinimal, WorkTemplate, MastodonTemplate, MinimalTemplate, WordCountTier, WorkError, ScrapingOptions, ao3_username_is_valid, days_since, relative_date_of, extract_search_blurbs, extract_work_ids, proxied_client, validate_selectors}, cache::{CacheEntry, CacheRecord, WorkExpiry}, feed::{RssTemplate, atom_feed}, platform::Platform, preview::PreviewTemplate, privacy::author_hash, session::CookieJar, stats::GlobalStats, i18n::get_translations, thumbnail::SvgTemplate, warnings::{ContentWarning, CwSeverity}, urls::{AO3_BASE_URL, ao3_work_url, ao3_user_url, host}}};
+use askama::Template;
+use axum::{Router, middleware::{self, Next}, extract::{State, Path, OriginalUri, Query, FromRequestParts, DefaultBodyLimit}, response::{IntoResponse, Response, Redirect, Html}, routing::{get, post, delete}, Json, TypedHeader, headers::UserAgent, http::{Uri, header, StatusCode, request::Parts, Request, HeaderMap}, async_trait, body::{Body, HttpBody, StreamBody}};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use clap::{Parser, Subcommand};
+use dashmap::{DashMap, DashSet, mapref::entry::Entry};
+use futures::StreamExt;
 use isbot::Bots;
-use moka::future::Cache;
+use moka::{future::Cache, notification::RemovalCause};
 use serde::{Deserialize, Serialize};
-use tower_http::normalize_path::NormalizePathLayer;
+use strsim::{jaro_winkler, levenshtein};
+use tokio::sync::mpsc;
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tower_http::{classify::ServerErrorsFailureClass, normalize_path::NormalizePathLayer, request_id::{MakeRequestId, RequestId, SetRequestIdLayer, PropagateRequestIdLayer}, trace::TraceLayer};
+use uuid::Uuid;
+use xxhash_rust::xxh3::xxh3_64;
+
+struct AppState {
+    work_cache: Cache<u64, CacheEntry>,
+    api_key: Option<String>,
+    client: reqwest::Client,
+    subscriptions: DashMap<u64, Vec<Subscription>>,
+    blocked_ids: HashSet<u64>,
+    seed_queue: mpsc::Sender<u64>,
+    duplicate_index: DashMap<(String, String), u64>,
+    access_counts: DashMap<u64, Arc<AtomicU64>>,
+    changelogs: DashMap<u64, WorkChangelog>,
+    access_log: Option<Arc<AccessLog>>,
+    scrape_failure_count: DashMap<u64, u32>,
+    poisoned_ids: DashSet<u64>,
+    /// Trigrams of each cached work's title and author, for `/cache/search`.
+    search_index: Arc<DashMap<String, Vec<u64>>>,
+    /// Works being cached in the background after a minimal first load,
+    /// with the minimal result to serve until they are.
+    first_loads: DashMap<u64, WorkMetadataMinimal>,
+    cookie_jar: Option<CookieJar>,
+    fandom_index: FandomIndex,
+    global_stats: RwLock<GlobalStats>,
+    user_agent_buckets: Cache<u64, Arc<Mutex<TokenBucket>>>,
+    user_agent_rate: f64,
+}
+
+impl AppState {
+    fn record_access(&self, id: u64) {
+        self.access_counts
+            .entry(id)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn access_count(&self, id: u64) -> u64 {
+        self.access_counts
+            .get(&id)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// The global stats, computed on first use once anything is cached
+    /// rather than left empty until the next hourly update.
+    fn global_stats(&self) -> RwLockReadGuard<'_, GlobalStats> {
+        if self.global_stats.read().unwrap().word_count_percentiles.is_empty() && self.work_cache.iter().next().is_some() {
+            self.update_global_stats();
+        }
+
+        self.global_stats.read().unwrap()
+    }
+
+    fn update_global_stats(&self) {
+        let word_counts = self.work_cache.iter()
+            .map(|(_, entry)| entry.metadata.words)
+            .collect::<Vec<u64>>();
+        let works = word_counts.len();
+
+        *self.global_stats.write().unwrap() = GlobalStats::from_word_counts(word_counts);
+
+        tracing::info!("Updated global stats from {} works", works);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Subscription {
+    webhook_url: String,
+    last_chapter: u16,
+}
+
+/// Serves embeds for AO3 works.
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Scrapes every work in an ID range, writing each one as a line of JSON.
+    BulkScrape {
+        /// The first work ID to scrape.
+        #[arg(long)]
+        start: u64,
+        /// The last work ID to scrape, inclusive.
+        #[arg(long)]
+        end: u64,
+        /// How many works to scrape at once.
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        #[arg(long)]
+        output: PathBuf,
+    },
+}
+
+async fn bulk_scrape(start: u64, end: u64, concurrency: usize, output: PathBuf) -> std::io::Result<()> {
+    let mut output = BufWriter::new(File::create(output)?);
+    let mut works = Box::pin(WorkMetadata::from_id_range_stream(start, end, concurrency, Arc::new(reqwest::Client::new())));
+    let (mut scraped, mut failed) = (0, 0);
+
+    while let Some((id, work)) = works.next().await {
+        match work {
+            Ok(work) => {
+                serde_json::to_writer(&mut output, &work)?;
+                writeln!(output)?;
+                scraped += 1;
+            },
+            Err(err) => {
+                eprintln!("{}", err.with_context(id));
+                failed += 1;
+            },
+        }
+    }
+
+    output.flush()?;
+    eprintln!("Scraped {} works, {} failed", scraped, failed);
+
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() {
+    let args = Args::parse();
+
+    #[cfg(feature = "opentelemetry")]
+    ao3_embed::telemetry::init();
+    #[cfg(not(feature = "opentelemetry"))]
     tracing_subscriber::fmt::fmt().with_file(true).init();
 
-    let state: Arc<Cache<u64, WorkMetadata>> = Arc::new(Cache::new(100));
+    validate_selectors();
 
-    let app = Router::new()
+    if let Some(Command::BulkScrape { start, end, concurrency, output }) = args.command {
+        if let Err(err) = bulk_scrape(start, end, concurrency, output).await {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let (seed_queue, seed_receiver) = mpsc::channel(1000);
+    let (expired_queue, expired_receiver) = mpsc::channel(1000);
+    let search_index = Arc::new(DashMap::new());
+    let evicted_index = search_index.clone();
+
+    let state = Arc::new(AppState {
+        work_cache: Cache::builder()
+            .max_capacity(100)
+            .expire_after(WorkExpiry)
+            .eviction_listener_with_queued_delivery_mode(move |id: Arc<u64>, entry: CacheEntry, cause| {
+                // Replaced entries are reindexed by `cache_entry` itself.
+                if cause != RemovalCause::Replaced {
+                    unindex_work(&evicted_index, &entry.metadata);
+                }
+
+                if !cause.was_evicted() {
+                    return;
+                }
+
+                tracing::info!(work_id = *id, cause = ?cause, "Cache entry evicted");
+
+                if cause == RemovalCause::Expired {
+                    // Dropped when the refresh task is behind, the next tick catches up.
+                    let _ = expired_queue.try_send(*id);
+                }
+            })
+            .build(),
+        api_key: env::var("API_KEY").ok().filter(|key| !key.is_empty()),
+        client: match env::var("SCRAPE_PROXY_URL") {
+            Ok(proxy_url) => proxied_client(&proxy_url).expect("invalid SCRAPE_PROXY_URL"),
+            Err(_) => reqwest::Client::new(),
+        },
+        subscriptions: DashMap::new(),
+        blocked_ids: env::var("BLOCKED_WORK_IDS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| id.trim().parse().ok())
+            .collect(),
+        seed_queue,
+        duplicate_index: DashMap::new(),
+        access_counts: DashMap::new(),
+        changelogs: DashMap::new(),
+        access_log: env::var("ACCESS_LOG_DB")
+            .ok()
+            .map(|path| Arc::new(AccessLog::open(&path).expect("could not open ACCESS_LOG_DB"))),
+        scrape_failure_count: DashMap::new(),
+        poisoned_ids: DashSet::new(),
+        search_index,
+        first_loads: DashMap::new(),
+        cookie_jar: env::var("AO3_SESSION_COOKIE")
+            .ok()
+            .filter(|session| !session.is_empty())
+            .map(|session| CookieJar::from_session(&session)),
+        fandom_index: FandomIndex::default(),
+        global_stats: RwLock::new(GlobalStats::default()),
+        user_agent_buckets: Cache::builder()
+            .max_capacity(100_000)
+            .time_to_idle(USER_AGENT_IDLE)
+            .build(),
+        user_agent_rate: env::var("UA_RATE_LIMIT_RPS")
+            .ok()
+            .and_then(|rps| rps.parse().ok())
+            .unwrap_or(2.0),
+    });
+
+    tokio::spawn(refresh_hot_works(state.clone(), expired_receiver));
+    tokio::spawn(seed_works(state.clone(), seed_receiver));
+    tokio::spawn(update_global_stats(state.clone()));
+
+    let max_body_bytes = env::var("MAX_REQUEST_BODY_BYTES")
+        .ok()
+        .and_then(|bytes| bytes.parse().ok())
+        .unwrap_or(1024 * 1024);
+
+    let posts = Router::new()
+        .route("/works/:id/subscribe", post(subscribe_response))
+        .route("/cache/invalidate", post(cache_invalidate_response))
+        .route("/cache/seed", post(cache_seed_response))
+        .route_layer(middleware::from_fn_with_state(max_body_bytes, request_size_limit))
+        .route("/cache/import", post(cache_import_response)
+            .layer(middleware::from_fn_with_state(max_body_bytes.max(IMPORT_BODY_BYTES), request_size_limit))
+            .layer(DefaultBodyLimit::max(IMPORT_BODY_BYTES)));
+
+    let embeds = Router::new()
         .route("/works/:id/*path", get(work_response))
         .route("/works/:id", get(work_response))
-        .route("/oembed/:id/:author/:words/:chapters/:total_chapters/:date", get(embed_response))
+        .route("/works/:id/thumbnail.svg", get(thumbnail_response))
+        .route("/works/:id/discord-embed", get(discord_embed_response))
+        .route("/oembed/v2/:id/:author/:words/:chapters/:total_chapters/:date", get(embed_response))
+        .route("/oembed/:id/:author/:words/:chapters/:total_chapters/:date", get(legacy_embed_response))
+        .route_layer(middleware::from_fn_with_state(state.clone(), user_agent_rate_limit));
+
+    let app = Router::new()
+        .route("/works/compare", get(compare_response))
+        .route("/works/:id/json", get(json_response))
+        .route("/works/:id/changelog", get(changelog_response))
+        .route("/works/:id/cite.bib", get(bibtex_response))
+        .route("/works/:id/mastodon-post", get(mastodon_post_response))
+        .route("/works/:id/bbcode", get(bbcode_response))
+        .route("/works/:id/related", get(related_response))
+        .route("/works/:id/table.html", get(html_table_response))
+        .route("/works/:id/info.nfo", get(nfo_response))
+        .route("/works/:id/text", get(plain_text_response))
+        .route("/works/:id/metadata.opf", get(opf_response))
+        .route("/works/:id/preview", get(preview_response))
+        .route("/works/:id/card", get(preview_response))
+        .route("/works/:id/failures", delete(clear_failures_response))
+        .route("/works/:id/kudos-users", get(kudos_users_response))
+        .route("/feed.atom", get(atom_feed_response))
+        .route("/feed.rss", get(rss_feed_response))
+        .route("/schema/work-metadata.json", get(schema_response))
+        .route("/cache/search", get(cache_search_response))
+        .route("/cache/export", get(cache_export_response))
+        .route("/stats/popular", get(popular_response))
+        .route("/stats/access-log", get(access_log_response))
+        .route("/stats/fandoms", get(fandom_stats_response))
+        .merge(embeds)
+        .merge(posts)
         .fallback(ao3_redirect)
         .layer(NormalizePathLayer::trim_trailing_slash())
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_request(log_request)
+                .on_response(log_response)
+                .on_failure(log_failure)
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(state);
 
     let addr = format!("[::]:{}", env::var("PORT").unwrap_or("3000".to_owned())).parse().unwrap();
@@ -29,9 +293,108 @@ async fn main() {
         .unwrap();
 }
 
-async fn ao3_redirect(OriginalUri(uri): OriginalUri) -> impl IntoResponse {
-    tracing::info!("Redirecting from: {}", &uri.to_string());
+#[derive(Clone, Copy)]
+struct MakeRequestUuid;
+
+impl MakeRequestId for MakeRequestUuid {
+    fn make_request_id<B>(&mut self, _: &Request<B>) -> Option<RequestId> {
+        let request_id = Uuid::new_v4().to_string().parse().ok()?;
+
+        Some(RequestId::new(request_id))
+    }
+}
+
+fn request_span(request: &Request<Body>) -> tracing::Span {
+    let request_id = request.extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default();
+
+    let span = tracing::info_span!("request", request_id = %request_id, work_id = tracing::field::Empty);
+
+    if let Some(id) = path_work_id(request.uri().path()) {
+        span.record("work_id", id);
+    }
+
+    span
+}
+
+/// Pulls the work ID out of `/works/:id/...`, `/oembed/:id/...` and
+/// `/oembed/v2/:id/...` paths.
+fn path_work_id(path: &str) -> Option<u64> {
+    let mut segments = path.trim_start_matches('/').split('/');
+
+    match (segments.next()?, segments.next()?) {
+        ("works", id) => id.parse().ok(),
+        ("oembed", "v2") => segments.next()?.parse().ok(),
+        ("oembed", id) => id.parse().ok(),
+        _ => None,
+    }
+}
+
+fn log_request(request: &Request<Body>, _: &tracing::Span) {
+    let user_agent = request.headers()
+        .get(header::USER_AGENT)
+        .and_then(|agent| agent.to_str().ok())
+        .unwrap_or("-");
+    let user_agent: String = user_agent.chars().take(40).collect();
 
+    tracing::info!("{} {} ({})", request.method(), request.uri().path(), user_agent);
+}
+
+fn log_response(response: &Response, latency: Duration, _: &tracing::Span) {
+    let size = response.body()
+        .size_hint()
+        .exact()
+        .map_or("-".to_owned(), |size| size.to_string());
+
+    tracing::info!("{} {}B in {}ms", response.status().as_u16(), size, latency.as_millis());
+}
+
+fn log_failure(failure: ServerErrorsFailureClass, latency: Duration, _: &tracing::Span) {
+    tracing::error!("Request failed: {} after {}ms", failure, latency.as_millis());
+}
+
+/// Cache imports are whole NDJSON dumps, so they get more room than other bodies.
+const IMPORT_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Rejects bodies over `limit` bytes with `413 Payload Too Large`, going by
+/// `Content-Length` when it's sent and counting the body as it arrives otherwise.
+async fn request_size_limit(
+    State(limit): State<usize>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, StatusCode> {
+    let content_length = request.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<usize>().ok());
+
+    if let Some(length) = content_length {
+        if length > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        return Ok(next.run(request).await);
+    }
+
+    let (parts, mut body) = request.into_parts();
+    let mut buffered = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        if buffered.len() + chunk.len() > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        buffered.extend_from_slice(&chunk);
+    }
+
+    Ok(next.run(Request::from_parts(parts, Body::from(buffered))).await)
+}
+
+async fn ao3_redirect(OriginalUri(uri): OriginalUri) -> impl IntoResponse {
     let redirect_uri = Uri::builder()
         .scheme("https")
         .authority("archiveofourown.org")
@@ -42,87 +405,1441 @@ async fn ao3_redirect(OriginalUri(uri): OriginalUri) -> impl IntoResponse {
     Redirect::temporary(&redirect_uri.to_string())
 }
 
+/// Seconds worth of requests a user agent can make in a burst.
+const USER_AGENT_BURST_SECS: f64 = 5.0;
+
+/// How long an unused bucket is kept. Any bucket idle for longer than the
+/// burst has refilled completely, so dropping it loses nothing.
+const USER_AGENT_IDLE: Duration = Duration::from_secs(60);
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64) -> Self {
+        TokenBucket { tokens: capacity, last_refill: Instant::now() }
+    }
+
+    /// Refills at `rate` tokens a second up to `capacity`, then takes a token if there is one.
+    fn try_take(&mut self, rate: f64, capacity: f64) -> bool {
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.last_refill).as_secs_f64() * rate).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Rate limits each user agent to `UA_RATE_LIMIT_RPS` on the embed routes,
+/// for scrapers that spread requests over many IPs. Browsers are only ever
+/// redirected to AO3 and are let through. User agents are keyed by a hash
+/// of the whole header so the keys stay small.
+async fn user_agent_rate_limit(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, StatusCode> {
+    let user_agent = request.headers()
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .unwrap_or_default();
+
+    if !Bots::default().is_bot(user_agent) && Platform::from_user_agent(user_agent) != Platform::Mastodon {
+        return Ok(next.run(request).await);
+    }
+
+    let prefix = user_agent.chars().take(32).collect::<String>();
+    let ua_hash = xxh3_64(user_agent.as_bytes());
+
+    let capacity = (state.user_agent_rate * USER_AGENT_BURST_SECS).max(1.0);
+
+    let bucket = state.user_agent_buckets
+        .get_with(ua_hash, async {
+            tracing::debug!(ua_hash = %format!("{:x}", ua_hash), ua_prefix = %prefix, "Tracking user agent");
+            Arc::new(Mutex::new(TokenBucket::new(capacity)))
+        })
+        .await;
+
+    let allowed = bucket.lock().unwrap().try_take(state.user_agent_rate, capacity);
+
+    if !allowed {
+        tracing::warn!(ua_hash = %format!("{:x}", ua_hash), ua_prefix = %prefix, "User agent rate limited");
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Extractor guarding operator endpoints behind `Authorization: Bearer <API_KEY>`.
+/// When no `API_KEY` is configured the endpoints are disabled entirely.
+struct ApiKey;
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ApiKey {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let Some(api_key) = &state.api_key else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+
+        let provided = parts.headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match provided {
+            Some(provided) if provided == api_key => Ok(ApiKey),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+/// Scrapes the work, logged in when a session cookie is configured.
+async fn scrape_work(state: &AppState, id: u64) -> Result<WorkMetadata, WorkError> {
+    match &state.cookie_jar {
+        Some(jar) => WorkMetadata::scrape_with_cookies(id, &state.client, jar).await,
+        None => WorkMetadata::scrape_with_options(id, &state.client, &ScrapingOptions::default()).await,
+    }
+}
+
+async fn cached_work(state: &AppState, id: u64) -> Result<WorkMetadata, WorkError> {
+    state.record_access(id);
+
+    if let Some(entry) = state.work_cache.get(&id) {
+        tracing::debug!("Cache hit for ID: {}", id);
+        return Ok(entry.metadata);
+    }
+
+    let mut work = scrape_work(state, id).await?;
+
+    enrich_work(state, &mut work).await;
+
+    cache_work(state, work.clone()).await;
+
+    tracing::debug!("Cached ID: {}", id);
+
+    Ok(work)
+}
+
+/// The optional secondary scrapes for a freshly scraped work.
+async fn enrich_work(state: &AppState, work: &mut WorkMetadata) {
+    if env_flag("ENRICH_RELATIONSHIPS") {
+        work.enrich_relationships(&state.client).await;
+    }
+}
+
+fn trigrams(s: &str) -> HashSet<String> {
+    let chars = s.to_lowercase().chars().collect::<Vec<char>>();
+
+    chars.windows(3)
+        .map(|window| window.iter().collect())
+        .collect()
+}
+
+fn work_trigrams(work: &WorkMetadata) -> HashSet<String> {
+    trigrams(&work.title)
+        .into_iter()
+        .chain(trigrams(&work.author))
+        .collect()
+}
+
+fn index_work(index: &DashMap<String, Vec<u64>>, work: &WorkMetadata) {
+    for trigram in work_trigrams(work) {
+        let mut ids = index.entry(trigram).or_default();
+
+        if !ids.contains(&work.id) {
+            ids.push(work.id);
+        }
+    }
+}
+
+fn unindex_work(index: &DashMap<String, Vec<u64>>, work: &WorkMetadata) {
+    for trigram in work_trigrams(work) {
+        if let Some(mut ids) = index.get_mut(&trigram) {
+            ids.retain(|&id| id != work.id);
+        }
+
+        index.remove_if(&trigram, |_, ids| ids.is_empty());
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+async fn cache_work(state: &AppState, work: WorkMetadata) {
+    cache_entry(state, CacheEntry::new(work)).await;
+}
+
+async fn cache_entry(state: &AppState, entry: CacheEntry) {
+    let work = &entry.metadata;
+    let id = work.id;
+
+    if let Some(existing_id) = state.duplicate_index.insert((normalize(&work.author), normalize(&work.title)), id) {
+        if existing_id != id {
+            tracing::warn!(work_id_new = id, work_id_existing = existing_id, "Possible duplicate work detected");
+        }
+    }
+
+    let cached = state.work_cache.get(&id);
+
+    if let Some(cached) = cached.as_ref().filter(|cached| cached.metadata.title != work.title || cached.metadata.author != work.author) {
+        unindex_work(&state.search_index, &cached.metadata);
+    }
+
+    index_work(&state.search_index, work);
+
+    match cached {
+        // Still store a new ETag for unchanged content, or it would never match.
+        Some(cached) if cached.content_hash == entry.content_hash && (entry.etag.is_none() || cached.etag == entry.etag) => {
+            tracing::info!("Unchanged ID: {}", id);
+            return;
+        },
+        Some(_) => {},
+        None => state.fandom_index.record(work),
+    }
+
+    state.work_cache.insert(id, entry).await;
+}
+
 #[derive(Deserialize)]
 struct WorkPath {
     pub id: u64,
     pub path: Option<String>, 
 }
 
+#[derive(Deserialize)]
+struct WorkQuery {
+    pub format: Option<String>,
+}
+
 async fn work_response(
     Path(WorkPath { id, path }): Path<WorkPath>,
-    State(work_cache): State<Arc<Cache<u64, WorkMetadata>>>,
+    Query(WorkQuery { format }): Query<WorkQuery>,
+    State(state): State<Arc<AppState>>,
     TypedHeader(user_agent): TypedHeader<UserAgent>,
+    headers: HeaderMap,
 ) -> Response {
+    if state.blocked_ids.contains(&id) {
+        tracing::info!("Blocked ID: {}", id);
+        return blocked_response();
+    }
+
     let bots = Bots::default();
-    
-    if !bots.is_bot(user_agent.as_str()) {
-        tracing::info!("IS BOT: Redirecting");
-        return Redirect::temporary(&format!("https://archiveofourown.org/works/{}/{}", id, path.unwrap_or_else(|| String::from("")))).into_response();
+    let platform = Platform::from_user_agent(user_agent.as_str());
+
+    if !bots.is_bot(user_agent.as_str()) && platform != Platform::Mastodon {
+        tracing::debug!("Redirecting non-bot request to AO3 for ID: {}", id);
+        return Redirect::temporary(&format!("{}/{}", ao3_work_url(id), path.unwrap_or_else(|| String::from("")))).into_response();
+    }
+
+    if state.poisoned_ids.contains(&id) {
+        return Redirect::temporary(&ao3_work_url(id)).into_response();
+    }
+
+    let start = Instant::now();
+    let cache_hit = state.work_cache.contains_key(&id);
+
+    if !cache_hit && env_flag("MINIMAL_FIRST_LOAD") {
+        if let Some(response) = minimal_first_load(&state, id).await {
+            return response;
+        }
     }
 
-    let work_cache = work_cache.clone();
+    let work = cached_work(&state, id).await;
+
+    log_access(&state, id, cache_hit, start.elapsed(), user_agent.as_str(), work.as_ref().ok());
 
-    let Some(work) = (match work_cache.get(&id) {
-        Some(work) => {
-            tracing::info!("Using cached for {}", id);
-            Some(work)
+    let work = match work {
+        Ok(work) => {
+            state.scrape_failure_count.remove(&id);
+            work
         },
-        None => match WorkMetadata::work(id).await {
-            Ok(work) => {
-                work_cache.insert(id, work.clone()).await;
+        Err(err) => {
+            match &err {
+                WorkError::ParseField { field, .. } => tracing::warn!(work_id = id, error = %err, field, "Could not retrieve meta, failed parsing field"),
+                _ => tracing::warn!(work_id = id, error = %err, "Could not retrieve meta"),
+            }
+            record_scrape_failure(&state, id, &err);
+            return err.status_code().into_response();
+        },
+    };
+
+    if env_flag("HIDE_EXPLICIT_CONTENT") && work.rating_is_explicit() {
+        return explicit_response(id);
+    }
 
-                tracing::info!("Caching ID: {}", id);
+    if format.as_deref() == Some("plaintext") {
+        return ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], work.to_plain_text()).into_response();
+    }
 
-                Some(work)
-            },
-            Err(_) => None,
-        }
-    }) else {
-        tracing::warn!("Could not retrieve meta.");
-        return Redirect::temporary(&format!("https://archiveofourown.org/works/{}/{}", id, path.unwrap_or_else(|| String::from("")))).into_response();
+    let etag = work_etag(&work, platform);
+
+    if if_none_match(&headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    let template: WorkTemplate = work.format_for_platform(platform);
+
+    let html = match platform {
+        Platform::Mastodon => MastodonTemplate::new(template).render_html(),
+        _ => template.render_html(),
+    };
+
+    let html = match html {
+        Ok(html) => html,
+        Err(err) => {
+            tracing::warn!(work_id = id, error = %err, "Error templating meta");
+            return err.status_code().into_response();
+        },
+    };
+
+    ([(header::ETAG, etag)], Html(html)).into_response()
+}
+
+/// Longest a first load's full scrape may run before it is given up on.
+const FIRST_LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Serves just the title and author of an uncached work while a full scrape
+/// caches it in the background. Requests arriving before that finishes are
+/// served the same minimal result. `None` when the minimal scrape fails, so
+/// the full metadata is scraped in the foreground instead.
+async fn minimal_first_load(state: &Arc<AppState>, id: u64) -> Option<Response> {
+    let in_flight = state.first_loads.get(&id).map(|minimal| minimal.clone());
+
+    let minimal = match in_flight {
+        Some(minimal) => minimal,
+        None => {
+            let minimal = WorkMetadata::scrape_minimal(id, &state.client, state.cookie_jar.as_ref())
+                .await
+                .map_err(|err| tracing::warn!(work_id = id, error = %err, "Could not retrieve minimal meta"))
+                .ok()?;
+
+            if let Entry::Vacant(entry) = state.first_loads.entry(id) {
+                entry.insert(minimal.clone());
+
+                let state = state.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(FIRST_LOAD_TIMEOUT, cached_work(&state, id)).await {
+                        Ok(Ok(_)) => {},
+                        Ok(Err(err)) => tracing::warn!(work_id = id, error = %err, "Could not complete first load"),
+                        Err(_) => tracing::warn!(work_id = id, "First load timed out"),
+                    }
+
+                    state.first_loads.remove(&id);
+                });
+            }
+
+            minimal
+        },
+    };
+
+    let html = MinimalTemplate::from(minimal).render_html().ok()?;
+
+    Some(([(header::CACHE_CONTROL, "no-cache")], Html(html)).into_response())
+}
+
+const MAX_SCRAPE_FAILURES: u32 = 5;
+
+/// Counts failures to make sense of the page towards poisoning the work.
+/// Network errors and rate limiting say nothing about the work itself.
+fn record_scrape_failure(state: &AppState, id: u64, err: &WorkError) {
+    if !err.is_parse_failure() {
+        return;
+    }
+
+    let consecutive_failures = {
+        let mut count = state.scrape_failure_count.entry(id).or_insert(0);
+        *count += 1;
+        *count
     };
 
-    let template: WorkTemplate = work.into();
+    if consecutive_failures >= MAX_SCRAPE_FAILURES && state.poisoned_ids.insert(id) {
+        tracing::error!(work_id = id, consecutive_failures, "Work repeatedly failed to scrape, redirecting instead");
+    }
+}
+
+async fn clear_failures_response(
+    _: ApiKey,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> StatusCode {
+    state.scrape_failure_count.remove(&id);
+    state.poisoned_ids.remove(&id);
+
+    StatusCode::NO_CONTENT
+}
+
+fn log_access(state: &AppState, id: u64, cache_hit: bool, elapsed: Duration, user_agent: &str, work: Option<&WorkMetadata>) {
+    let Some(access_log) = state.access_log.clone() else {
+        return;
+    };
 
-    let Ok(html) = template.render_html() else {
-        tracing::warn!("Error templating meta.");
-        return Redirect::temporary(&format!("https://archiveofourown.org/works/{}/{}", id, path.unwrap_or_else(|| String::from("")))).into_response();
+    let record = AccessRecord {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() as i64),
+        work_id: id,
+        cache_hit,
+        response_time_ms: elapsed.as_millis() as u64,
+        user_agent_hash: format!("{:x}", xxh3_64(user_agent.as_bytes())),
+        author_hash: work
+            .filter(|_| env_flag("ANALYTICS_PRIVACY"))
+            .map(|work| format!("{:x}", work.privacy_hash().author_hash)),
     };
 
-    Html(html).into_response()
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = access_log.record(&record) {
+            tracing::warn!("Could not write access log: {}", err);
+        }
+    });
+}
+
+fn work_etag(work: &WorkMetadata, platform: Platform) -> String {
+    let json = serde_json::to_string(work).unwrap_or_default();
+
+    format!("\"{:x}\"", xxh3_64(format!("{:?}{}", platform, json).as_bytes()))
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == etag || tag == "*")
+}
+
+fn blocked_response() -> Response {
+    (
+        StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        [(header::LINK, format!("<{}>; rel=\"blocked-by\"", AO3_BASE_URL))],
+        Html("<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><title>Unavailable For Legal Reasons</title></head><body><p>This work is unavailable for legal reasons.</p></body></html>"),
+    ).into_response()
+}
+
+/// A bare content warning for explicit works, without any of the work's metadata.
+fn explicit_response(id: u64) -> Response {
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><title>Explicit Work</title><meta name=\"robots\" content=\"noindex\"></head><body><p>This work is rated Explicit and may contain content unsuitable for some readers.</p><p><a href=\"{}\">Continue to AO3</a></p></body></html>",
+        ao3_work_url(id),
+    )).into_response()
 }
 
+/// `WorkMetadata` as served from `/works/:id/json`, with the values computed
+/// from it so downstream tools don't have to.
 #[derive(Serialize)]
-struct EmbedResponse {
-    pub version: &'static str,
-    #[serde(rename = "type")]
-    pub embed_type: &'static str,
-    pub author_name: String,
-    pub author_url: String,
-    pub provider_name: String,
-    pub provider_url: String,
+struct WorkMetadataResponse {
+    #[serde(flatten)]
+    pub metadata: WorkMetadata,
+    pub ao3_url: String,
+    pub is_complete: bool,
+    pub word_count_tier: WordCountTier,
+    pub human_readable_date: String,
+    pub reading_time_minutes: u64,
+    pub request_count: u64,
+    pub cw_severity: CwSeverity,
+    pub content_warnings: Vec<ContentWarning>,
+    pub relationship_characters: Vec<String>,
+    pub chapters: Vec<(String, String)>,
+    pub chapters_remaining: Option<u16>,
+    pub is_crossover: bool,
+    pub crossover_fandom_count: usize,
+    pub original_work_url: Option<String>,
+    pub fandom_rank: Option<u64>,
+    pub word_count_percentile: f64,
+}
+
+/// Looks up a work for one of the per-work endpoints, turning blocked works
+/// and scrape failures into their error responses.
+async fn served_work(state: &AppState, id: u64) -> Result<WorkMetadata, Response> {
+    if state.blocked_ids.contains(&id) {
+        return Err(blocked_response());
+    }
+
+    cached_work(state, id).await.map_err(|err| {
+        tracing::warn!(work_id = id, error = %err, "Could not retrieve meta");
+        err.status_code().into_response()
+    })
+}
+
+impl WorkMetadataResponse {
+    fn new(state: &AppState, metadata: WorkMetadata) -> Self {
+        WorkMetadataResponse {
+            ao3_url: metadata.ao3_url(),
+            is_complete: metadata.is_complete(),
+            word_count_tier: metadata.word_count_tier(),
+            human_readable_date: metadata.human_readable_date(),
+            reading_time_minutes: metadata.reading_time_minutes(),
+            request_count: state.access_count(metadata.id),
+            cw_severity: metadata.cw_severity(),
+            content_warnings: metadata.content_warnings(),
+            relationship_characters: metadata.all_relationship_characters(),
+            chapters: metadata.canonical_chapter_urls(),
+            chapters_remaining: metadata.chapters_remaining(),
+            is_crossover: metadata.is_crossover(),
+            crossover_fandom_count: metadata.crossover_fandoms().len(),
+            original_work_url: metadata.original_work_url(),
+            fandom_rank: metadata.primary_fandom_rank(&state.fandom_index),
+            word_count_percentile: metadata.word_count_percentile(&state.global_stats()),
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Snake,
+    Camel,
+    JsonApi,
 }
 
 #[derive(Deserialize)]
-struct EmbedRequest {
-    pub id: u64,
-    pub author: String,
-    pub words: u64,
-    pub chapters: u16,
-    pub total_chapters: String,
-    pub date: String,
+struct FormatQuery {
+    #[serde(default)]
+    pub format: Format,
 }
 
-async fn embed_response(
-    Path(EmbedRequest { id, author, words, chapters, total_chapters, date }): Path<EmbedRequest>,
-) -> Json<EmbedResponse> {
-    tracing::info!("Embed Request ID: {}", id);
-    Json(EmbedResponse {
+async fn json_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Query(FormatQuery { format }): Query<FormatQuery>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok(match format {
+        Format::Snake => stream_json(WorkMetadataResponse::new(&state, work)),
+        Format::Camel => {
+            let json = serde_json::to_value(WorkMetadataResponse::new(&state, work)).unwrap_or_default();
+
+            Json(camel_case_keys(json)).into_response()
+        },
+        Format::JsonApi => (
+            [(header::CONTENT_TYPE, "application/vnd.api+json")],
+            work.to_jsonapi_document().to_string(),
+        ).into_response(),
+    })
+}
+
+/// Serializes `value` on a blocking thread straight into the response body,
+/// so the first bytes go out before serialization finishes.
+fn stream_json<T: Serialize + Send + 'static>(value: T) -> Response {
+    let (writer, reader) = tokio::io::duplex(8 * 1024);
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = serde_json::to_writer(SyncIoBridge::new(writer), &value) {
+            tracing::warn!("Could not stream JSON: {}", err);
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        StreamBody::new(ReaderStream::new(reader)),
+    ).into_response()
+}
+
+fn camel_case_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(fields) => fields.into_iter()
+            .map(|(key, value)| {
+                let mut parts = key.split('_');
+                let first = parts.next().unwrap_or_default().to_owned();
+                let key = parts.fold(first, |mut key, part| {
+                    let mut chars = part.chars();
+                    key.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                    key.push_str(chars.as_str());
+                    key
+                });
+
+                (key, camel_case_keys(value))
+            })
+            .collect(),
+        serde_json::Value::Array(values) => values.into_iter().map(camel_case_keys).collect(),
+        value => value,
+    }
+}
+
+async fn thumbnail_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    let svg = SvgTemplate::from(&work).render().map_err(|err| {
+        tracing::warn!(work_id = id, error = %err, "Error templating thumbnail");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        svg,
+    ).into_response())
+}
+
+async fn preview_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    let html = PreviewTemplate::from(&work).render().map_err(|err| {
+        tracing::warn!(work_id = id, error = %err, "Error templating preview");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok(Html(html).into_response())
+}
+
+async fn discord_embed_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DiscordEmbed>, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok(Json(work.to_discord_embed()))
+}
+
+async fn bibtex_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, String::from("application/x-bibtex; charset=utf-8")),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"work_{}.bib\"", id)),
+        ],
+        work.to_bibtex(),
+    ).into_response())
+}
+
+async fn kudos_users_response(
+    _: ApiKey,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<String>>, Response> {
+    let Some(jar) = &state.cookie_jar else {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    };
+
+    if state.blocked_ids.contains(&id) {
+        return Err(blocked_response());
+    }
+
+    WorkMetadata::scrape_kudos_users(id, &state.client, jar)
+        .await
+        .map(Json)
+        .map_err(|err| {
+            tracing::warn!(work_id = id, error = %err, "Could not retrieve kudos");
+            err.status_code().into_response()
+        })
+}
+
+async fn mastodon_post_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        work.to_mastodon_status(),
+    ).into_response())
+}
+
+async fn bbcode_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        work.to_bbcode(),
+    ).into_response())
+}
+
+async fn related_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<WorkMetadata>>, Response> {
+    let work = served_work(&state, id).await?;
+
+    let related = work.fetch_related_works(&state.client).await.map_err(|err| {
+        tracing::warn!(work_id = id, error = %err, "Could not retrieve related works");
+        err.status_code().into_response()
+    })?;
+
+    Ok(Json(related.into_iter().filter(|work| !state.blocked_ids.contains(&work.id)).collect()))
+}
+
+async fn html_table_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        work.to_html_table(),
+    ).into_response())
+}
+
+async fn nfo_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        work.to_nfo(),
+    ).into_response())
+}
+
+async fn plain_text_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        work.to_plain_text(),
+    ).into_response())
+}
+
+async fn opf_response(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let work = served_work(&state, id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/oebps-package+xml; charset=utf-8")],
+        work.to_opf(),
+    ).into_response())
+}
+
+async fn schema_response() -> Response {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=86400")],
+        Json(schemars::schema_for!(WorkMetadata)),
+    ).into_response()
+}
+
+#[derive(Serialize)]
+struct EmbedResponse {
+    pub version: &'static str,
+    #[serde(rename = "type")]
+    pub embed_type: &'static str,
+    pub author_name: String,
+    pub author_url: String,
+    pub provider_name: String,
+    pub provider_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct EmbedRequest {
+    pub id: u64,
+    pub author: String,
+    pub words: u64,
+    pub chapters: u16,
+    pub total_chapters: String,
+    pub date: String,
+}
+
+#[derive(Deserialize)]
+struct EmbedQuery {
+    /// The account the pseud in the path belongs to.
+    pub account: Option<String>,
+    pub comments: Option<u64>,
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub podfic: bool,
+}
+
+/// The current embed route, with the author URL-safe base64 encoded.
+async fn embed_response(
+    Path(request): Path<EmbedRequest>,
+    Query(query): Query<EmbedQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmbedResponse>, StatusCode> {
+    let author = URL_SAFE_NO_PAD.decode(&request.author)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    Ok(embed(&state, EmbedRequest { author, ..request }, query))
+}
+
+/// Embed URLs from before authors were encoded, with the raw author name.
+async fn legacy_embed_response(
+    Path(request): Path<EmbedRequest>,
+    Query(query): Query<EmbedQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Json<EmbedResponse> {
+    embed(&state, request, query)
+}
+
+fn embed(
+    state: &AppState,
+    EmbedRequest { id, author, words, chapters, total_chapters, date }: EmbedRequest,
+    EmbedQuery { account, comments, lang, podfic }: EmbedQuery,
+) -> Json<EmbedResponse> {
+    // Embed URLs without an account, from orphaned works or before pseuds
+    // were split out, only link the users listing.
+    let provider_url = match account {
+        Some(account) if ao3_username_is_valid(&account) => ao3_user_url(&account),
+        Some(account) => {
+            if env_flag("ANALYTICS_PRIVACY") {
+                tracing::warn!(author_hash = %format!("{:x}", author_hash(&account)), "Invalid AO3 username");
+            } else {
+                tracing::warn!("Invalid AO3 username: {}", account);
+            }
+            format!("{}/users/", AO3_BASE_URL)
+        },
+        None => format!("{}/users/", AO3_BASE_URL),
+    };
+
+    let lang = lang
+        .or_else(|| env::var("EMBED_LANG").ok())
+        .unwrap_or_else(|| String::from("en"));
+    let translations = get_translations(&lang);
+
+    // Only the remaining count for incomplete works with a known total.
+    let remaining = total_chapters.parse::<u16>()
+        .ok()
+        .map(|total| total.saturating_sub(chapters))
+        .filter(|&remaining| remaining > 0)
+        .map(|remaining| format!(" ({} remaining)", remaining))
+        .unwrap_or_default();
+
+    // Recently updated works show how long ago that was instead of the date.
+    let date = match days_since(&date) {
+        Some(days) if days <= 30 => relative_date_of(&date).unwrap_or(date),
+        _ => date,
+    };
+
+    // Podfics are recordings, their word count (often 0) says little about them.
+    let length = if podfic {
+        String::from("🎙️ audio")
+    } else {
+        let percentile = state.global_stats().word_count_percentile(words);
+
+        if percentile > 75.0 {
+            format!("{} · {} {} (longer than {:.0}% of works)", WordCountTier::from_words(words), words, translations.words, percentile)
+        } else {
+            format!("{} · {} {}", WordCountTier::from_words(words), words, translations.words)
+        }
+    };
+
+    let mut author_name = format!("{} ✏️ {} / {} {}{} 📚 {} 🕒", length, chapters, total_chapters, translations.chapters, remaining, date);
+
+    if let Some(comments) = comments.filter(|&c| c > 0) {
+        author_name.push_str(&format!(" {} {} 💬", comments, translations.comments));
+    }
+
+    Json(EmbedResponse {
         version: "1.0",
         embed_type: "rich",
-        author_name: format!("{} ✏️ {} / {} 📚 {} 🕒", words, chapters, total_chapters, date),
-        author_url: format!("https://archiveofourown.org/works/{}", urlencoding::encode(&id.to_string())),
-        provider_name: author.clone(),
-        provider_url: format!("https://archiveofourown.org/users/{}", urlencoding::encode(&author)),
+        author_name,
+        author_url: ao3_work_url(id),
+        provider_name: author,
+        provider_url,
+        html: Some(format!(
+            "<iframe src=\"{}/works/{}/card\" width=\"600\" height=\"400\" frameborder=\"0\"></iframe>",
+            host(),
+            id,
+        )),
+        width: Some(600),
+        height: Some(400),
     })
 }
+
+async fn atom_feed_response(
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let mut entries = state.work_cache.iter()
+        .map(|(_, entry)| entry)
+        .collect::<Vec<CacheEntry>>();
+
+    entries.sort_by(|a, b| b.fetched_at.cmp(&a.fetched_at));
+    entries.truncate(10);
+
+    let host = host();
+
+    (
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        atom_feed(&host, &entries),
+    ).into_response()
+}
+
+async fn rss_feed_response(
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let mut entries = state.work_cache.iter()
+        .map(|(_, entry)| entry)
+        .collect::<Vec<CacheEntry>>();
+
+    entries.sort_by(|a, b| b.fetched_at.cmp(&a.fetched_at));
+    entries.truncate(20);
+
+    let Ok(rss) = RssTemplate::new(&host(), &entries).render() else {
+        tracing::warn!("Error templating RSS feed.");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    (
+        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        rss,
+    ).into_response()
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    pub q: String,
+}
+
+async fn cache_search_response(
+    _: ApiKey,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<WorkMetadata>> {
+    let q = q.to_lowercase();
+    let query_trigrams = trigrams(&q);
+
+    // Queries too short for trigrams fall back to scanning the cache.
+    if query_trigrams.is_empty() {
+        let results = state.work_cache.iter()
+            .map(|(_, entry)| entry.metadata)
+            .filter(|work| work.title.to_lowercase().contains(&q) || work.author.to_lowercase().contains(&q))
+            .take(20)
+            .collect();
+
+        return Json(results);
+    }
+
+    let mut candidates: Option<HashSet<u64>> = None;
+
+    for trigram in &query_trigrams {
+        let ids = state.search_index
+            .get(trigram)
+            .map(|ids| ids.iter().copied().collect::<HashSet<u64>>())
+            .unwrap_or_default();
+
+        candidates = Some(match candidates {
+            Some(candidates) => candidates.intersection(&ids).copied().collect(),
+            None => ids,
+        });
+    }
+
+    // Sharing every trigram doesn't mean containing the query, so check.
+    let mut results = candidates.unwrap_or_default()
+        .into_iter()
+        .filter_map(|id| state.work_cache.get(&id))
+        .map(|entry| entry.metadata)
+        .filter(|work| work.title.to_lowercase().contains(&q) || work.author.to_lowercase().contains(&q))
+        .collect::<Vec<WorkMetadata>>();
+
+    let score = |work: &WorkMetadata| jaro_winkler(&work.title.to_lowercase(), &q).max(jaro_winkler(&work.author.to_lowercase(), &q));
+    results.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    results.truncate(20);
+
+    Json(results)
+}
+
+#[derive(Deserialize)]
+struct InvalidateRequest {
+    pub ids: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct InvalidateResponse {
+    pub invalidated: Vec<u64>,
+    pub not_found: Vec<u64>,
+}
+
+async fn cache_invalidate_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+    Json(InvalidateRequest { ids }): Json<InvalidateRequest>,
+) -> Response {
+    if ids.len() > 100 {
+        return (StatusCode::BAD_REQUEST, "at most 100 ids may be invalidated at once").into_response();
+    }
+
+    let mut invalidated = Vec::new();
+    let mut not_found = Vec::new();
+
+    for id in ids {
+        if state.work_cache.contains_key(&id) {
+            state.work_cache.invalidate(&id).await;
+            invalidated.push(id);
+        } else {
+            not_found.push(id);
+        }
+    }
+
+    tracing::info!("Invalidated {} cache entries", invalidated.len());
+
+    Json(InvalidateResponse { invalidated, not_found }).into_response()
+}
+
+async fn cache_export_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let (writer, reader) = tokio::io::duplex(8 * 1024);
+
+    // Written a line at a time like `stream_json`, so exporting a large cache
+    // never holds the whole dump in memory.
+    tokio::task::spawn_blocking(move || {
+        let mut writer = SyncIoBridge::new(writer);
+
+        for (_, entry) in state.work_cache.iter() {
+            let Ok(mut line) = serde_json::to_vec(&CacheRecord::from(entry)) else {
+                continue;
+            };
+            line.push(b'\n');
+
+            if let Err(err) = writer.write_all(&line) {
+                tracing::warn!("Could not stream cache export: {}", err);
+                break;
+            }
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        StreamBody::new(ReaderStream::new(reader)),
+    ).into_response()
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+    pub imported: usize,
+    /// Records skipped because they are already past their TTL.
+    pub expired: usize,
+    pub failed: usize,
+}
+
+async fn cache_import_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+    body: String,
+) -> Json<ImportResponse> {
+    let mut imported = 0;
+    let mut expired = 0;
+    let mut failed = 0;
+
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<CacheRecord>(line) {
+            Ok(record) => {
+                let entry = CacheEntry::from(record);
+
+                if entry.is_expired() {
+                    expired += 1;
+                    continue;
+                }
+
+                cache_entry(&state, entry).await;
+                imported += 1;
+            },
+            Err(err) => {
+                tracing::warn!("Could not import cache line: {}", err);
+                failed += 1;
+            },
+        }
+    }
+
+    tracing::info!("Imported {} cache entries, skipped {} expired", imported, expired);
+
+    Json(ImportResponse { imported, expired, failed })
+}
+
+#[derive(Serialize)]
+struct SeedResponse {
+    pub cached: usize,
+    pub enqueued: usize,
+}
+
+async fn cache_seed_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+    body: String,
+) -> Json<SeedResponse> {
+    // Works whose blurbs parse are cached straight away, the rest are scraped.
+    let blurbs = extract_search_blurbs(&body)
+        .into_iter()
+        .filter(|work| !state.blocked_ids.contains(&work.id))
+        .collect::<Vec<WorkMetadata>>();
+    let cached = blurbs.len();
+    let cached_ids = blurbs.iter().map(|work| work.id).collect::<HashSet<u64>>();
+
+    for work in blurbs {
+        warn_similar_titles(&state, &work);
+        cache_work(&state, work).await;
+    }
+
+    let enqueued = extract_work_ids(&body)
+        .into_iter()
+        .filter(|id| !cached_ids.contains(id) && !state.work_cache.contains_key(id))
+        .filter(|&id| state.seed_queue.try_send(id).is_ok())
+        .count();
+
+    tracing::info!("Cached {} and enqueued {} works for seeding", cached, enqueued);
+
+    Json(SeedResponse { cached, enqueued })
+}
+
+/// Warns about cached works by the same author in a shared fandom whose
+/// titles are within a couple of edits of `work`'s.
+fn warn_similar_titles(state: &AppState, work: &WorkMetadata) {
+    for (_, entry) in state.work_cache.iter() {
+        let other = &entry.metadata;
+
+        if other.id == work.id || other.author != work.author || !other.fandoms.iter().any(|fandom| work.fandoms.contains(fandom)) {
+            continue;
+        }
+
+        if levenshtein(&other.title, &work.title) < 3 {
+            tracing::warn!(ids = ?[work.id, other.id], "Possible duplicate titles in same fandom");
+        }
+    }
+}
+
+/// Scrapes works queued by `/cache/seed` one at a time, pausing between
+/// requests so bulk seeding stays polite to AO3.
+async fn seed_works(state: Arc<AppState>, mut seed_receiver: mpsc::Receiver<u64>) {
+    while let Some(id) = seed_receiver.recv().await {
+        if state.work_cache.contains_key(&id) {
+            continue;
+        }
+
+        match scrape_work(&state, id).await {
+            Ok(work) => {
+                warn_similar_titles(&state, &work);
+                cache_work(&state, work).await;
+                tracing::info!("Seeded ID: {}", id);
+            },
+            Err(err) => tracing::warn!(work_id = id, error = %err, "Could not seed"),
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeRequest {
+    pub webhook_url: String,
+}
+
+async fn subscribe_response(
+    _: ApiKey,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(SubscribeRequest { webhook_url }): Json<SubscribeRequest>,
+) -> Response {
+    let work = match cached_work(&state, id).await {
+        Ok(work) => work,
+        Err(err) => {
+            tracing::warn!(work_id = id, error = %err, "Could not retrieve meta");
+            return err.status_code().into_response();
+        },
+    };
+
+    state.subscriptions
+        .entry(id)
+        .or_default()
+        .push(Subscription { webhook_url, last_chapter: work.chapter });
+
+    tracing::info!("Subscribed webhook to {}", id);
+
+    StatusCode::CREATED.into_response()
+}
+
+/// Periodically re-scrapes every work with a webhook subscription, keeping
+/// their cache entries warm and notifying subscribers of new chapters.
+async fn refresh_hot_works(state: Arc<AppState>, mut expired: mpsc::Receiver<u64>) {
+    let interval = env::var("REFRESH_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(900);
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {},
+            Some(id) = expired.recv() => {
+                // Hot works are re-scraped as soon as they expire rather than
+                // waiting for the next tick or a request.
+                if state.subscriptions.contains_key(&id) {
+                    refresh_work(&state, id).await;
+                }
+
+                continue;
+            },
+        }
+
+        let mut ids = state.subscriptions.iter()
+            .map(|subscriptions| *subscriptions.key())
+            .collect::<Vec<u64>>();
+
+        // Works that have never been cached are refreshed first.
+        ids.sort_by_cached_key(|id| {
+            Reverse(state.work_cache.get(id).map_or(u32::MAX, |entry| entry.metadata.freshness_score()))
+        });
+
+        for id in ids {
+            refresh_work(&state, id).await;
+        }
+    }
+}
+
+async fn update_global_stats(state: Arc<AppState>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(60 * 60));
+
+    loop {
+        ticker.tick().await;
+        state.update_global_stats();
+    }
+}
+
+async fn refresh_work(state: &AppState, id: u64) {
+    if env_flag("LIGHTWEIGHT_REFRESH") && revision_unchanged(state, id).await {
+        tracing::info!("Unrevised ID: {}", id);
+        return;
+    }
+
+    let last_etag = state.work_cache.get(&id).and_then(|entry| entry.etag);
+
+    let (mut work, etag) = match WorkMetadata::scrape_if_modified(id, &state.client, state.cookie_jar.as_ref(), last_etag.as_deref()).await {
+        Ok(Some(modified)) => modified,
+        Ok(None) => {
+            tracing::info!("Unmodified ID: {}", id);
+            return;
+        },
+        Err(err) => {
+            tracing::warn!(work_id = id, error = %err, "Could not refresh");
+            return;
+        },
+    };
+
+    enrich_work(state, &mut work).await;
+
+    if let Some(previous) = state.work_cache.get(&id) {
+        let changes = previous.metadata.diff(&work);
+
+        if !changes.is_empty() {
+            state.changelogs
+                .entry(id)
+                .or_insert_with(|| WorkChangelog::new(id))
+                .record(changes);
+        }
+    }
+
+    let mut entry = CacheEntry::new(work.clone());
+    entry.etag = etag;
+    cache_entry(state, entry).await;
+
+    tracing::info!("Refreshed ID: {}", id);
+
+    notify_subscribers(state, &work).await;
+}
+
+/// Whether AO3 reports a revision date before the cached copy's, in which
+/// case the full scrape can be skipped. Both are day dates, so a work revised
+/// on the same day as the cached copy may have changed since and counts as
+/// revised.
+async fn revision_unchanged(state: &AppState, id: u64) -> bool {
+    let Some(cached) = state.work_cache.get(&id).and_then(|entry| entry.metadata.revised_at) else {
+        return false;
+    };
+
+    match WorkMetadata::fetch_revised_at(id, &state.client, state.cookie_jar.as_ref()).await {
+        Ok(Some(revised_at)) => revised_at < cached,
+        _ => false,
+    }
+}
+
+async fn notify_subscribers(state: &AppState, work: &WorkMetadata) {
+    let webhook_urls = match state.subscriptions.get(&work.id) {
+        Some(subscriptions) => subscriptions.iter()
+            .filter(|subscription| work.chapter > subscription.last_chapter)
+            .map(|subscription| subscription.webhook_url.clone())
+            .collect::<Vec<String>>(),
+        None => return,
+    };
+
+    for webhook_url in webhook_urls {
+        tracing::info!("Notifying webhook of new chapter for {}", work.id);
+
+        if let Err(err) = state.client.post(&webhook_url).json(work).send().await {
+            tracing::warn!("Webhook delivery failed for {}: {}", work.id, err);
+        }
+    }
+
+    if let Some(mut subscriptions) = state.subscriptions.get_mut(&work.id) {
+        for subscription in subscriptions.iter_mut() {
+            subscription.last_chapter = subscription.last_chapter.max(work.chapter);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PopularWork {
+    pub work_id: u64,
+    pub requests: u64,
+}
+
+async fn popular_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<PopularWork>> {
+    let mut popular = state.access_counts.iter()
+        .map(|count| PopularWork { work_id: *count.key(), requests: count.load(Ordering::Relaxed) })
+        .collect::<Vec<PopularWork>>();
+
+    popular.sort_by(|a, b| b.requests.cmp(&a.requests));
+    popular.truncate(20);
+
+    Json(popular)
+}
+
+#[derive(Deserialize)]
+struct AccessLogQuery {
+    pub work_id: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+async fn access_log_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+    Query(AccessLogQuery { work_id, limit }): Query<AccessLogQuery>,
+) -> Result<Json<Vec<AccessRecord>>, StatusCode> {
+    let Some(access_log) = state.access_log.clone() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let limit = limit.unwrap_or(100).min(1000);
+
+    tokio::task::spawn_blocking(move || access_log.recent(work_id, limit))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .map_err(|err| {
+            tracing::warn!("Could not read access log: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+#[derive(Deserialize)]
+struct FandomStatsQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct FandomCount {
+    pub fandom: String,
+    pub count: u64,
+}
+
+async fn fandom_stats_response(
+    _: ApiKey,
+    State(state): State<Arc<AppState>>,
+    Query(FandomStatsQuery { limit }): Query<FandomStatsQuery>,
+) -> Json<Vec<FandomCount>> {
+    let limit = limit.unwrap_or(20).min(1000);
+
+    Json(state.fandom_index.top(limit)
+        .into_iter()
+        .map(|(fandom, count)| FandomCount { fandom, count })
+        .collect())
+}
+
+async fn changelog_response(
+    _: ApiKey,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<WorkChangelog>, StatusCode> {
+    state.changelogs
+        .get(&id)
+        .map(|changelog| Json(changelog.clone()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    pub id1: u64,
+    pub id2: u64,
+}
+
+#[derive(Serialize)]
+struct CompareSide {
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work: Option<WorkMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CompareResponse {
+    pub id1: CompareSide,
+    pub id2: CompareSide,
+    pub changes: Option<Vec<MetadataChange>>,
+}
+
+async fn compare_side(state: &AppState, id: u64) -> CompareSide {
+    if state.blocked_ids.contains(&id) {
+        return CompareSide { id, work: None, error: Some(String::from("the work is blocked")) };
+    }
+
+    let result = match tokio::time::timeout(Duration::from_secs(5), cached_work(state, id)).await {
+        Ok(result) => result.map_err(|err| err.to_string()),
+        Err(_) => Err(String::from("timed out fetching the work")),
+    };
+
+    match result {
+        Ok(work) => CompareSide { id, work: Some(work), error: None },
+        Err(error) => CompareSide { id, work: None, error: Some(error) },
+    }
+}
+
+async fn compare_response(
+    _: ApiKey,
+    Query(CompareQuery { id1, id2 }): Query<CompareQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Json<CompareResponse> {
+    let (id1, id2) = tokio::join!(compare_side(&state, id1), compare_side(&state, id2));
+
+    let changes = match (&id1.work, &id2.work) {
+        (Some(a), Some(b)) => Some(a.diff(b)),
+        _ => None,
+    };
+
+    Json(CompareResponse { id1, id2, changes })
+}
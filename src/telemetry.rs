@@ -0,0 +1,42 @@
+use std::{collections::HashMap, env};
+
+use opentelemetry::{global, runtime, sdk::propagation::TraceContextPropagator};
+use opentelemetry_otlp::WithExportConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::prelude::*;
+
+/// Installs the fmt subscriber alongside an OTLP exporter sending spans to
+/// `OTLP_ENDPOINT`, or the exporter's default endpoint when unset.
+pub fn init() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let mut exporter = opentelemetry_otlp::new_exporter().tonic();
+
+    if let Ok(endpoint) = env::var("OTLP_ENDPOINT") {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .install_batch(runtime::Tokio)
+        .expect("could not install the OTLP pipeline");
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_file(true))
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .init();
+}
+
+/// W3C `traceparent` headers for the current span, to pass along on outgoing requests.
+pub fn trace_context_headers() -> HeaderMap {
+    let context = tracing::Span::current().context();
+    let mut carrier = HashMap::new();
+
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut carrier));
+
+    carrier.into_iter()
+        .filter_map(|(name, value)| Some((HeaderName::try_from(name).ok()?, HeaderValue::try_from(value).ok()?)))
+        .collect()
+}